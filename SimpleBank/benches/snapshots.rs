@@ -0,0 +1,63 @@
+// Cost of snapshotting a `BankState`, run with `cargo bench`.
+//
+// `deep_clone_*` copies the same data held in std maps, i.e. what every
+// snapshot used to cost before the state was backed by persistent maps.
+
+#![feature(test)]
+
+extern crate test;
+
+use num_bigint::BigInt;
+use simple_bank::bank::BankState;
+use std::collections::BTreeMap;
+use test::{black_box, Bencher};
+
+fn bank_with(accounts: usize) -> BankState {
+    let mut bank_state = BankState::new();
+    for i in 0..accounts {
+        bank_state.balances.insert(format!("account{i}"), BigInt::from(i) * 1_000_000_007u64);
+    }
+    bank_state
+}
+
+fn clone(b: &mut Bencher, accounts: usize) {
+    let bank_state = bank_with(accounts);
+    b.iter(|| black_box(bank_state.clone()));
+}
+
+fn clone_and_deposit(b: &mut Bencher, accounts: usize) {
+    let bank_state = bank_with(accounts);
+    b.iter(|| {
+        let mut snapshot = bank_state.clone();
+        snapshot.deposit("account0".into(), 1.into()).unwrap();
+        black_box(snapshot)
+    });
+}
+
+fn diff_after_deposit(b: &mut Bencher, accounts: usize) {
+    let bank_state = bank_with(accounts);
+    let mut snapshot = bank_state.clone();
+    snapshot.deposit("account0".into(), 1.into()).unwrap();
+    b.iter(|| black_box(bank_state.diff(&snapshot).count()));
+}
+
+fn deep_clone(b: &mut Bencher, accounts: usize) {
+    let balances: BTreeMap<String, BigInt> = bank_with(accounts).balances.into_iter().collect();
+    b.iter(|| black_box(balances.clone()));
+}
+
+#[bench] fn clone_10k(b: &mut Bencher)   { clone(b, 10_000) }
+#[bench] fn clone_100k(b: &mut Bencher)  { clone(b, 100_000) }
+#[bench] fn clone_1m(b: &mut Bencher)    { clone(b, 1_000_000) }
+
+#[bench] fn clone_and_deposit_10k(b: &mut Bencher)  { clone_and_deposit(b, 10_000) }
+#[bench] fn clone_and_deposit_100k(b: &mut Bencher) { clone_and_deposit(b, 100_000) }
+#[bench] fn clone_and_deposit_1m(b: &mut Bencher)   { clone_and_deposit(b, 1_000_000) }
+
+#[bench] fn diff_after_deposit_10k(b: &mut Bencher)  { diff_after_deposit(b, 10_000) }
+#[bench] fn diff_after_deposit_100k(b: &mut Bencher) { diff_after_deposit(b, 100_000) }
+#[bench] fn diff_after_deposit_1m(b: &mut Bencher)   { diff_after_deposit(b, 1_000_000) }
+
+#[bench] fn deep_clone_10k(b: &mut Bencher)  { deep_clone(b, 10_000) }
+#[bench] fn deep_clone_100k(b: &mut Bencher) { deep_clone(b, 100_000) }
+#[bench] fn deep_clone_1m(b: &mut Bencher)   { deep_clone(b, 1_000_000) }
@@ -2,18 +2,85 @@
 #![allow(unused_variables)]
 
 use num_bigint::BigInt;
+use std::fmt;
 use std::str::FromStr;
 use std::str::{SplitWhitespace};
-use serde::{Serialize};
+use serde::{Serialize, Deserialize};
+use serde_with::{DisplayFromStr, PickFirst, Same};
+use serde_with_macros::serde_as;
 
 
-#[derive(Clone, Debug, Serialize)]
+// amounts are written as strings, as in the state log, but action logs from
+// before the journal have them in num-bigint's own form, `[sign, [digits]]`,
+// which is still read, see `Journal::from_actions`
+type AmountFormat = PickFirst<(DisplayFromStr, Same)>;
+
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Action {
-    Deposit        { depositor: String, amount: BigInt },
-    Withdraw       { withdrawer: String, amount: BigInt },
-    Transfer       { sender: String, receiver: String, amount: BigInt },
-    BuyInvestment  { buyer: String, amount: BigInt },
-    SellInvestment { seller: String, investment_id: BigInt },
+    Deposit {
+        depositor: String,
+        #[serde_as(as = "AmountFormat")]
+        amount: BigInt,
+    },
+    Withdraw {
+        withdrawer: String,
+        #[serde_as(as = "AmountFormat")]
+        amount: BigInt,
+    },
+    Transfer {
+        sender: String,
+        receiver: String,
+        #[serde_as(as = "AmountFormat")]
+        amount: BigInt,
+    },
+    BuyInvestment {
+        buyer: String,
+        #[serde_as(as = "AmountFormat")]
+        amount: BigInt,
+    },
+    SellInvestment {
+        seller: String,
+        #[serde_as(as = "AmountFormat")]
+        investment_id: BigInt,
+    },
+    Approve {
+        owner: String,
+        spender: String,
+        #[serde_as(as = "AmountFormat")]
+        amount: BigInt,
+    },
+    TransferFrom {
+        spender: String,
+        owner: String,
+        receiver: String,
+        #[serde_as(as = "AmountFormat")]
+        amount: BigInt,
+    },
+    ScheduleTransfer {
+        sender: String,
+        receiver: String,
+        #[serde_as(as = "AmountFormat")]
+        amount: BigInt,
+        #[serde_as(as = "AmountFormat")]
+        at: BigInt,
+        #[serde_as(as = "Option<AmountFormat>")]
+        every: Option<BigInt>,
+    },
+    Tick,
+    SetLimit {
+        account: String,
+        #[serde_as(as = "AmountFormat")]
+        max: BigInt,
+        #[serde_as(as = "AmountFormat")]
+        blocks: BigInt,
+    },
+    Freeze {
+        account: String,
+    },
+    Unfreeze {
+        account: String,
+    },
 }
 
 // parsing helpers
@@ -85,9 +152,110 @@ impl FromStr for Action {
 
                 Ok(Action::SellInvestment { seller, investment_id })
             },
+            "approve" => {
+                let owner: String   = next_arg(&mut parts)?.to_string();
+                let spender: String = next_arg(&mut parts)?.to_string();
+                let amount: BigInt  = next_arg(&mut parts)?
+                    .parse().map_err(|_| "Invalid amount")?;
+
+                no_more_args(&mut parts)?;
+
+                Ok(Action::Approve { owner, spender, amount })
+            },
+            "transfer_from" => {
+                let spender: String  = next_arg(&mut parts)?.to_string();
+                let owner: String    = next_arg(&mut parts)?.to_string();
+                let receiver: String = next_arg(&mut parts)?.to_string();
+                let amount: BigInt   = next_arg(&mut parts)?
+                    .parse().map_err(|_| "Invalid amount")?;
+
+                no_more_args(&mut parts)?;
+
+                Ok(Action::TransferFrom { spender, owner, receiver, amount })
+            },
+            "schedule_transfer" => {
+                let sender: String   = next_arg(&mut parts)?.to_string();
+                let receiver: String = next_arg(&mut parts)?.to_string();
+                let amount: BigInt   = next_arg(&mut parts)?
+                    .parse().map_err(|_| "Invalid amount")?;
+                let at: BigInt       = next_arg(&mut parts)?
+                    .parse().map_err(|_| "Invalid height")?;
+                // the period is optional
+                let every: Option<BigInt> = parts.next()
+                    .map(|every| every.parse().map_err(|_| "Invalid period"))
+                    .transpose()?;
+
+                no_more_args(&mut parts)?;
+
+                Ok(Action::ScheduleTransfer { sender, receiver, amount, at, every })
+            },
+            "tick" => {
+                no_more_args(&mut parts)?;
+
+                Ok(Action::Tick)
+            },
+            "set_limit" => {
+                let account: String = next_arg(&mut parts)?.to_string();
+                let max: BigInt     = next_arg(&mut parts)?
+                    .parse().map_err(|_| "Invalid amount")?;
+                let blocks: BigInt  = next_arg(&mut parts)?
+                    .parse().map_err(|_| "Invalid number of blocks")?;
+
+                no_more_args(&mut parts)?;
+
+                Ok(Action::SetLimit { account, max, blocks })
+            },
+            "freeze" => {
+                let account: String = next_arg(&mut parts)?.to_string();
+
+                no_more_args(&mut parts)?;
+
+                Ok(Action::Freeze { account })
+            },
+            "unfreeze" => {
+                let account: String = next_arg(&mut parts)?.to_string();
+
+                no_more_args(&mut parts)?;
+
+                Ok(Action::Unfreeze { account })
+            },
             _ => {
-                Err(format!("Unknown command: {}", cmd))
+                Err(format!("Unknown command: {cmd}"))
             },
         }
     }
 }
+
+// same syntax as the REPL, so that printed actions can be pasted back
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Deposit { depositor, amount } =>
+                write!(f, "deposit {depositor} {amount}"),
+            Action::Withdraw { withdrawer, amount } =>
+                write!(f, "withdraw {withdrawer} {amount}"),
+            Action::Transfer { sender, receiver, amount } =>
+                write!(f, "transfer {sender} {receiver} {amount}"),
+            Action::BuyInvestment { buyer, amount } =>
+                write!(f, "buy_investment {buyer} {amount}"),
+            Action::SellInvestment { seller, investment_id } =>
+                write!(f, "sell_investment {seller} {investment_id}"),
+            Action::Approve { owner, spender, amount } =>
+                write!(f, "approve {owner} {spender} {amount}"),
+            Action::TransferFrom { spender, owner, receiver, amount } =>
+                write!(f, "transfer_from {spender} {owner} {receiver} {amount}"),
+            Action::ScheduleTransfer { sender, receiver, amount, at, every: None } =>
+                write!(f, "schedule_transfer {sender} {receiver} {amount} {at}"),
+            Action::ScheduleTransfer { sender, receiver, amount, at, every: Some(every) } =>
+                write!(f, "schedule_transfer {sender} {receiver} {amount} {at} {every}"),
+            Action::Tick =>
+                write!(f, "tick"),
+            Action::SetLimit { account, max, blocks } =>
+                write!(f, "set_limit {account} {max} {blocks}"),
+            Action::Freeze { account } =>
+                write!(f, "freeze {account}"),
+            Action::Unfreeze { account } =>
+                write!(f, "unfreeze {account}"),
+        }
+    }
+}
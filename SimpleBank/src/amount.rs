@@ -0,0 +1,49 @@
+// Numeric types usable for amounts in a `BankState`.
+//
+// `BigInt` mirrors the unbounded `int` of the Quint spec, while `u64` and
+// `u128` behave like the integers of production ledgers (e.g. `Uint128` in
+// cosmwasm). Arithmetic on amounts is always checked, so bounded backends
+// report overflows as errors instead of wrapping. Amounts are written as
+// decimal strings in logs, hence `FromStr`.
+
+use num_bigint::BigInt;
+use num_traits::{CheckedAdd, CheckedSub, Zero};
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use crate::bank::ErrorMsg;
+
+pub trait Amount:
+    Clone + Debug + Display + Ord + Zero + CheckedAdd + CheckedSub + TryFrom<BigInt, Error: Display>
+    + FromStr<Err: Display>
+{
+    /// Converts an amount picked in a trace or typed in the REPL.
+    fn from_bigint(amount: BigInt) -> Result<Self, ErrorMsg> {
+        if amount <= BigInt::zero() {
+            return Err("Amount should be greater than zero".to_string());
+        }
+
+        Self::try_from(amount).map_err(|_| "Amount is out of range".to_string())
+    }
+
+    /// Same for allowances, which unlike amounts may be zero.
+    fn allowance_from_bigint(allowance: BigInt) -> Result<Self, ErrorMsg> {
+        if allowance < BigInt::zero() {
+            return Err("Allowance can't be negative".to_string());
+        }
+
+        Self::try_from(allowance).map_err(|_| "Amount is out of range".to_string())
+    }
+
+    /// Same for rate limits, a limit of zero blocking every debit.
+    fn limit_from_bigint(limit: BigInt) -> Result<Self, ErrorMsg> {
+        if limit < BigInt::zero() {
+            return Err("Limit can't be negative".to_string());
+        }
+
+        Self::try_from(limit).map_err(|_| "Amount is out of range".to_string())
+    }
+}
+
+impl Amount for BigInt {}
+impl Amount for u64 {}
+impl Amount for u128 {}
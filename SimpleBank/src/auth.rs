@@ -0,0 +1,124 @@
+// Who may do what with a `BankState`.
+//
+// The bank itself mirrors the spec and trusts the accounts named in actions,
+// so permissions are checked in front of it, against the authenticated
+// caller:
+// - anyone may deposit to any account,
+// - only the owner of an account may debit it, i.e. withdraw, transfer,
+//   buy or sell investments, or approve spenders,
+// - only a spender may use its allowance, with `transfer_from`, the bank
+//   checking the allowance itself,
+// - admins may freeze accounts, which can then no longer be debited,
+//   set rate limits, and advance the height with `tick`.
+//
+// Frozen accounts are part of the `BankState`, so that freezes are journaled
+// like any other action, while admins are configured when starting the bank.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use crate::action::Action;
+use crate::bank::BankState;
+
+/// Reasons for denying an action, like `ContractError::Unauthorized` in cosmwasm contracts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Unauthorized {
+    NotLoggedIn,
+    NotOwner {
+        caller:  String,
+        account: String,
+    },
+    NotSpender {
+        caller:  String,
+        spender: String,
+    },
+    NotAdmin {
+        caller: String,
+    },
+    Frozen {
+        account: String,
+    },
+    BadCredentials {
+        account: String,
+    },
+}
+
+impl fmt::Display for Unauthorized {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unauthorized::NotLoggedIn =>
+                write!(f, "Unauthorized: not logged in"),
+            Unauthorized::NotOwner { caller, account } =>
+                write!(f, "Unauthorized: {caller} can't debit {account}"),
+            Unauthorized::NotSpender { caller, spender } =>
+                write!(f, "Unauthorized: {caller} can't use the allowance of {spender}"),
+            Unauthorized::NotAdmin { caller } =>
+                write!(f, "Unauthorized: {caller} is not an admin"),
+            Unauthorized::Frozen { account } =>
+                write!(f, "Unauthorized: {account} is frozen"),
+            Unauthorized::BadCredentials { account } =>
+                write!(f, "Unauthorized: bad credentials for {account}"),
+        }
+    }
+}
+
+impl std::error::Error for Unauthorized {}
+
+#[derive(Clone, Debug, Default)]
+pub struct Permissions {
+    admins: BTreeSet<String>,
+}
+
+impl Permissions {
+    pub fn new<S: Into<String>>(admins: impl IntoIterator<Item = S>) -> Self {
+        Permissions {
+            admins: admins.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn is_admin(&self, account: &str) -> bool {
+        self.admins.contains(account)
+    }
+
+    fn check_admin(&self, caller: &str) -> Result<(), Unauthorized> {
+        if self.is_admin(caller) {
+            Ok(())
+        }
+        else {
+            Err(Unauthorized::NotAdmin { caller: caller.to_string() })
+        }
+    }
+
+    /// Checks that `caller` may perform `action` on `state`.
+    pub fn authorize<A>(&self, state: &BankState<A>, caller: &str, action: &Action) -> Result<(), Unauthorized> {
+        let account =
+            match action {
+                Action::Deposit { .. }                  => return Ok(()),
+                Action::Tick                            => return self.check_admin(caller),
+                Action::SetLimit { .. }                 => return self.check_admin(caller),
+                Action::Freeze { .. }                   => return self.check_admin(caller),
+                Action::Unfreeze { .. }                 => return self.check_admin(caller),
+                Action::Withdraw { withdrawer, .. }     => withdrawer,
+                Action::Transfer { sender, .. }         => sender,
+                Action::BuyInvestment { buyer, .. }     => buyer,
+                Action::SellInvestment { seller, .. }   => seller,
+                Action::Approve { owner, .. }           => owner,
+                Action::TransferFrom { owner, .. }      => owner,
+                Action::ScheduleTransfer { sender, .. } => sender,
+            };
+
+        if state.frozen.contains(account) {
+            return Err(Unauthorized::Frozen { account: account.clone() });
+        }
+
+        match action {
+            Action::TransferFrom { spender, .. } if caller != spender =>
+                Err(Unauthorized::NotSpender { caller: caller.to_string(), spender: spender.clone() }),
+            Action::TransferFrom { .. } =>
+                Ok(()),
+            _ if caller != account =>
+                Err(Unauthorized::NotOwner { caller: caller.to_string(), account: account.clone() }),
+            _ =>
+                Ok(()),
+        }
+    }
+}
@@ -2,152 +2,780 @@
 #![allow(unused_variables)]
 
 use serde_with_macros::serde_as;
-use serde_with::DisplayFromStr;
+use serde_with::{As, DisplayFromStr, Same, TryFromInto};
 use num_bigint::BigInt;
-use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use imbl::{OrdMap, OrdSet, Vector};
+use imbl::ordmap::DiffItem;
+use imbl::ordset;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+use crate::action::Action;
+use crate::amount::Amount;
+use crate::event::Event;
 // use itf::value::{Value, Map};
 // use itf::value::BigInt as ITFBigInt;
 
 pub type ErrorMsg = String;
 
+/// Events of a successful operation, see `Event`.
+pub type Events<A = BigInt> = Vec<Event<A>>;
+
 #[serde_as]
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Investment {
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(serialize = "A: Display", deserialize = "A: Amount"))]
+pub struct Investment<A = BigInt> {
     pub owner: String,
+    #[serde_as(serialize_as = "DisplayFromStr", deserialize_as = "TryFromInto<BigInt>")]
+    pub amount: A,
+}
+
+/// A transfer executed by `tick` once the height reaches `at`.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(serialize = "A: Display", deserialize = "A: Amount"))]
+pub struct Schedule<A = BigInt> {
+    pub sender:   String,
+    pub receiver: String,
+    #[serde_as(serialize_as = "DisplayFromStr", deserialize_as = "TryFromInto<BigInt>")]
+    pub amount:   A,
     #[serde_as(serialize_as = "DisplayFromStr")]
-    pub amount: BigInt,
+    pub at:       BigInt,
+    /// Blocks between executions, `None` for a one-off transfer
+    #[serde_as(serialize_as = "Option<DisplayFromStr>", deserialize_as = "itf::de::Option<BigInt>")]
+    pub every:    Option<BigInt>,
+}
+
+/// A scheduled transfer which could not be executed when due.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FailedTransfer {
+    #[serde_as(serialize_as = "DisplayFromStr")]
+    pub id:     BigInt,
+    #[serde_as(serialize_as = "DisplayFromStr")]
+    pub height: BigInt,
+    pub reason: String,
+}
+
+/// At most `max` may leave an account within any `blocks` consecutive blocks.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(serialize = "A: Display", deserialize = "A: Amount"))]
+pub struct RateLimit<A = BigInt> {
+    #[serde_as(serialize_as = "DisplayFromStr", deserialize_as = "TryFromInto<BigInt>")]
+    pub max:    A,
+    #[serde_as(serialize_as = "DisplayFromStr")]
+    pub blocks: BigInt,
+}
+
+/// An amount which left a rate-limited account at `height`.
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(serialize = "A: Display", deserialize = "A: Amount"))]
+pub struct Spend<A = BigInt> {
+    #[serde_as(serialize_as = "DisplayFromStr")]
+    pub height: BigInt,
+    #[serde_as(serialize_as = "DisplayFromStr", deserialize_as = "TryFromInto<BigInt>")]
+    pub amount: A,
+}
+
+/// A withdrawal or transfer denied by the rate limit of `account`.
+///
+/// Only `available` may leave the account until the window resets, at height
+/// `resets_at`, when everything spent in it has expired. It is `None` when the
+/// amount exceeds the limit itself, and no reset would let it through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateLimited<A = BigInt> {
+    pub account:   String,
+    pub available: A,
+    pub resets_at: Option<BigInt>,
+}
+
+impl<A: Display> Display for RateLimited<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.resets_at {
+            Some(resets_at) => write!(f, "Rate limited: {} may move up to {} until the window resets at height {}",
+                                      self.account, self.available, resets_at),
+            None            => write!(f, "Rate limited: {} may move up to {}, the amount exceeds the limit",
+                                      self.account, self.available),
+        }
+    }
+}
+
+impl<A: Display> From<RateLimited<A>> for ErrorMsg {
+    fn from(limited: RateLimited<A>) -> Self {
+        limited.to_string()
+    }
 }
 
 // NOTE(flupe):
 //  currently, the state is an exact copy of the Quint state (or the converse)
 //  we could imagine having a different representation for the Rust state
 //  and an equivalence relation instead.
+//
+// Both maps are persistent: cloning a state is O(1) and the clones share
+// every entry until one of them is modified, so snapshots are cheap.
 #[serde_as]
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct BankState {
-    #[serde_as(serialize_as = "HashMap<_, DisplayFromStr>")]
-    pub balances: HashMap<String, BigInt>,
-    #[serde_as(serialize_as = "HashMap<DisplayFromStr, _>")]
-    pub investments: HashMap<BigInt, Investment>,
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(serialize = "A: Display", deserialize = "A: Amount"))]
+pub struct BankState<A = BigInt> {
+    #[serde(serialize_with = "serialize_balances", deserialize_with = "deserialize_balances")]
+    pub balances: OrdMap<String, A>,
+    #[serde(serialize_with = "serialize_by_id")]
+    pub investments: OrdMap<BigInt, Investment<A>>,
     #[serde_as(serialize_as = "DisplayFromStr")]
     pub next_id: BigInt,
+    /// How much a spender may transfer out of an account, keyed by (owner, spender)
+    #[serde(default, serialize_with = "serialize_allowances", deserialize_with = "deserialize_allowances")]
+    pub allowances: OrdMap<(String, String), A>,
+    /// Logical block height, advanced by `tick`
+    #[serde(default)]
+    #[serde_as(serialize_as = "DisplayFromStr")]
+    pub height: BigInt,
+    #[serde(default, serialize_with = "serialize_by_id")]
+    pub schedules: OrdMap<BigInt, Schedule<A>>,
+    #[serde(default)]
+    #[serde_as(serialize_as = "DisplayFromStr")]
+    pub next_schedule_id: BigInt,
+    #[serde(default)]
+    pub failed_transfers: Vector<FailedTransfer>,
+    #[serde(default)]
+    pub limits: OrdMap<String, RateLimit<A>>,
+    /// What left rate-limited accounts within their window, oldest first
+    #[serde(default)]
+    pub spent: OrdMap<String, Vector<Spend<A>>>,
+    /// Accounts frozen by an admin, which may no longer be debited
+    #[serde(default)]
+    pub frozen: OrdSet<String>,
 }
 
+// serde_with only has adapters for std maps, persistent maps are handled here
 
-impl BankState {
-    pub fn new() -> Self {
+fn serialize_balances<S: Serializer, A: Display>(balances: &OrdMap<String, A>, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_map(balances.iter().map(|(account, amount)| (account, amount.to_string())))
+}
+
+fn deserialize_balances<'de, D: Deserializer<'de>, A: Amount>(d: D) -> Result<OrdMap<String, A>, D::Error> {
+    let balances: BTreeMap<String, A> = As::<BTreeMap<Same, TryFromInto<BigInt>>>::deserialize(d)?;
+    Ok(balances.into_iter().collect())
+}
+
+fn serialize_by_id<S: Serializer, V: Serialize>(map: &OrdMap<BigInt, V>, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_map(map.iter().map(|(id, value)| (id.to_string(), value)))
+}
+
+// JSON keys are strings, so allowances are logged as owner -> spender -> amount
+fn serialize_allowances<S: Serializer, A: Display>(allowances: &OrdMap<(String, String), A>, s: S) -> Result<S::Ok, S::Error> {
+    let mut nested: BTreeMap<&String, BTreeMap<&String, String>> = BTreeMap::new();
+    for ((owner, spender), amount) in allowances {
+        nested.entry(owner).or_default().insert(spender, amount.to_string());
+    }
+    nested.serialize(s)
+}
+
+fn deserialize_allowances<'de, D: Deserializer<'de>, A: Amount>(d: D) -> Result<OrdMap<(String, String), A>, D::Error> {
+    let allowances: BTreeMap<(String, String), A> = As::<BTreeMap<Same, TryFromInto<BigInt>>>::deserialize(d)?;
+    Ok(allowances.into_iter().collect())
+}
+
+/// A difference between two snapshots of a `BankState`.
+#[derive(Debug, PartialEq)]
+pub enum Change<'a, A> {
+    Balance {
+        account: &'a String,
+        before:  Option<&'a A>,
+        after:   Option<&'a A>,
+    },
+    Investment {
+        id:     &'a BigInt,
+        before: Option<&'a Investment<A>>,
+        after:  Option<&'a Investment<A>>,
+    },
+    Allowance {
+        owner_spender: &'a (String, String),
+        before:        Option<&'a A>,
+        after:         Option<&'a A>,
+    },
+    Height {
+        before: &'a BigInt,
+        after:  &'a BigInt,
+    },
+    Schedule {
+        id:     &'a BigInt,
+        before: Option<&'a Schedule<A>>,
+        after:  Option<&'a Schedule<A>>,
+    },
+    /// A transfer which failed in between, i.e. a failure of `other` that
+    /// `self` has no failure of the same schedule at the same height for
+    FailedTransfer(&'a FailedTransfer),
+    Limit {
+        account: &'a String,
+        before:  Option<&'a RateLimit<A>>,
+        after:   Option<&'a RateLimit<A>>,
+    },
+    Spent {
+        account: &'a String,
+        before:  Option<&'a Vector<Spend<A>>>,
+        after:   Option<&'a Vector<Spend<A>>>,
+    },
+    Frozen {
+        account: &'a String,
+        frozen:  bool,
+    },
+}
+
+fn split<'a, K, V>(item: DiffItem<'a, 'a, K, V>) -> (&'a K, Option<&'a V>, Option<&'a V>) {
+    match item {
+        DiffItem::Add(k, after)                     => (k, None, Some(after)),
+        DiffItem::Update { old: (k, before), new }  => (k, Some(before), Some(new.1)),
+        DiffItem::Remove(k, before)                 => (k, Some(before), None),
+    }
+}
+
+fn overflow() -> ErrorMsg {
+    "Balance would overflow".to_string()
+}
+
+impl<A: Amount> Default for BankState<A> {
+    fn default() -> Self {
         BankState {
-            balances:    HashMap::new(),
-            investments: HashMap::new(),
+            balances:    OrdMap::new(),
+            investments: OrdMap::new(),
             next_id:     BigInt::from(0),
+            allowances:  OrdMap::new(),
+            height:           BigInt::from(0),
+            schedules:        OrdMap::new(),
+            next_schedule_id: BigInt::from(0),
+            failed_transfers: Vector::new(),
+            limits:           OrdMap::new(),
+            spent:            OrdMap::new(),
+            frozen:           OrdSet::new(),
         }
     }
+}
 
-    pub fn deposit(&mut self, depositor: String, amount: BigInt) -> Result<(), ErrorMsg> {
-        if amount <= BigInt::from(0) {
-            return Err("Amount should be greater than zero".to_string());
+impl BankState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<A: Amount> BankState<A> {
+    /// Changes turning `self` into `other`.
+    ///
+    /// Parts of the maps shared by both snapshots are skipped without being
+    /// visited, so diffing a state against a recent snapshot of itself is
+    /// proportional to the number of changes rather than the number of accounts.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = Change<'a, A>> + 'a {
+        let balances = self.balances.diff(&other.balances).map(|item| {
+            let (account, before, after) = split(item);
+            Change::Balance { account, before, after }
+        });
+
+        let investments = self.investments.diff(&other.investments).map(|item| {
+            let (id, before, after) = split(item);
+            Change::Investment { id, before, after }
+        });
+
+        let allowances = self.allowances.diff(&other.allowances).map(|item| {
+            let (owner_spender, before, after) = split(item);
+            Change::Allowance { owner_spender, before, after }
+        });
+
+        let height = (self.height != other.height)
+            .then_some(Change::Height { before: &self.height, after: &other.height });
+
+        let schedules = self.schedules.diff(&other.schedules).map(|item| {
+            let (id, before, after) = split(item);
+            Change::Schedule { id, before, after }
+        });
+
+        // a schedule fails at most once per height, so the two identify a failure
+        let failed_before: BTreeSet<_> = self.failed_transfers.iter()
+            .map(|failure| (&failure.id, &failure.height))
+            .collect();
+        let failed_transfers = other.failed_transfers.iter()
+            .filter(move |failure| !failed_before.contains(&(&failure.id, &failure.height)))
+            .map(Change::FailedTransfer);
+
+        let limits = self.limits.diff(&other.limits).map(|item| {
+            let (account, before, after) = split(item);
+            Change::Limit { account, before, after }
+        });
+
+        let spent = self.spent.diff(&other.spent).map(|item| {
+            let (account, before, after) = split(item);
+            Change::Spent { account, before, after }
+        });
+
+        let frozen = self.frozen.diff(&other.frozen).map(|item| match item {
+            ordset::DiffItem::Add(account)    => Change::Frozen { account, frozen: true },
+            ordset::DiffItem::Remove(account) => Change::Frozen { account, frozen: false },
+        });
+
+        balances
+            .chain(investments)
+            .chain(allowances)
+            .chain(height)
+            .chain(schedules)
+            .chain(failed_transfers)
+            .chain(limits)
+            .chain(spent)
+            .chain(frozen)
+    }
+
+    pub fn apply(&mut self, action: Action) -> Result<Events<A>, ErrorMsg> {
+        match action {
+            Action::Deposit { depositor, amount } =>
+                self.deposit(depositor, A::from_bigint(amount)?),
+            Action::Withdraw { withdrawer, amount } =>
+                self.withdraw(withdrawer, A::from_bigint(amount)?),
+            Action::Transfer { sender, receiver, amount } =>
+                self.transfer(sender, receiver, A::from_bigint(amount)?),
+            Action::BuyInvestment { buyer, amount } =>
+                self.buy_investment(buyer, A::from_bigint(amount)?),
+            Action::SellInvestment { seller, investment_id } =>
+                self.sell_investment(seller, investment_id),
+            Action::Approve { owner, spender, amount } =>
+                self.approve(owner, spender, A::allowance_from_bigint(amount)?),
+            Action::TransferFrom { spender, owner, receiver, amount } =>
+                self.transfer_from(spender, owner, receiver, A::from_bigint(amount)?),
+            Action::ScheduleTransfer { sender, receiver, amount, at, every } =>
+                self.schedule_transfer(sender, receiver, A::from_bigint(amount)?, at, every),
+            Action::Tick =>
+                self.tick(),
+            Action::SetLimit { account, max, blocks } =>
+                self.set_limit(account, A::limit_from_bigint(max)?, blocks),
+            Action::Freeze { account } =>
+                self.freeze(account),
+            Action::Unfreeze { account } =>
+                self.unfreeze(account),
         }
+    }
 
-        self.balances.entry(depositor)
-            .and_modify(|curr| *curr += amount.clone())
-            .or_insert(amount);
+    /// Replays an event, as recorded in a journal.
+    ///
+    /// No business rule is checked here (the operation that emitted the event
+    /// already did), only that the event is consistent with the state, so that
+    /// a tampered or truncated journal is reported instead of rebuilt.
+    pub fn apply_event(&mut self, event: &Event<A>) -> Result<(), ErrorMsg> {
+        match event {
+            Event::BalanceCredited { account, amount } => {
+                let balance = self.credited(account, amount)?;
+                self.balances.insert(account.clone(), balance);
+            }
+            Event::BalanceDebited { account, amount } => {
+                let balance = self.balances.get(account)
+                                  .ok_or(format!("Debit of unknown account {account}"))?;
+                let balance = Self::debited(balance, amount)?;
+                self.balances.insert(account.clone(), balance);
+            }
+            Event::InvestmentCreated { id, owner, amount } => {
+                if self.investments.contains_key(id) {
+                    return Err(format!("Investment {id} already exists"));
+                }
+                self.investments.insert(id.clone(), Investment { owner: owner.clone(), amount: amount.clone() });
+                self.next_id = self.next_id.clone().max(id + 1);
+            }
+            Event::InvestmentClosed { id, owner, amount } => {
+                let closed = Investment { owner: owner.clone(), amount: amount.clone() };
+                if self.investments.get(id) != Some(&closed) {
+                    return Err(format!("Investment {id} does not match the one closed"));
+                }
+                self.investments.remove(id);
+            }
+            Event::AllowanceChanged { owner, spender, amount } => {
+                self.allowances.insert((owner.clone(), spender.clone()), amount.clone());
+            }
+            Event::TransferScheduled { id, sender, receiver, amount, at, every } => {
+                if self.schedules.contains_key(id) {
+                    return Err(format!("Schedule {id} already exists"));
+                }
+                self.schedules.insert(id.clone(), Schedule {
+                    sender:   sender.clone(),
+                    receiver: receiver.clone(),
+                    amount:   amount.clone(),
+                    at:       at.clone(),
+                    every:    every.clone(),
+                });
+                self.next_schedule_id = self.next_schedule_id.clone().max(id + 1);
+            }
+            Event::HeightAdvanced { height } => {
+                self.height = height.clone();
+            }
+            Event::TransferFailed { id, height, reason } => {
+                self.failed_transfers.push_back(FailedTransfer {
+                    id:     id.clone(),
+                    height: height.clone(),
+                    reason: reason.clone(),
+                });
+            }
+            Event::TransferRescheduled { id, at } => {
+                let schedule = self.schedules.get_mut(id)
+                                   .ok_or(format!("No schedule {id} to reschedule"))?;
+                schedule.at = at.clone();
+            }
+            Event::ScheduleEnded { id } => {
+                self.schedules.remove(id)
+                    .ok_or(format!("No schedule {id} to end"))?;
+            }
+            Event::RateLimitSet { account, max, blocks } => {
+                self.limits.insert(account.clone(), RateLimit { max: max.clone(), blocks: blocks.clone() });
+            }
+            Event::RateLimitUsed { account, amount } => {
+                if !self.limits.contains_key(account) {
+                    return Err(format!("{account} is not rate limited"));
+                }
+                let window = self.spent_after(account, amount);
+                self.spent.insert(account.clone(), window);
+            }
+            Event::AccountFrozen { account } => {
+                self.frozen.insert(account.clone());
+            }
+            Event::AccountUnfrozen { account } => {
+                self.frozen.remove(account);
+            }
+        }
 
         Ok(())
     }
 
-    pub fn withdraw(&mut self, withdrawer: String, amount: BigInt) -> Result<(), ErrorMsg> {
-        if amount <= BigInt::from(0) {
+    // balance of `account` once credited with `amount`, missing accounts start at zero
+    fn credited(&self, account: &str, amount: &A) -> Result<A, ErrorMsg> {
+        self.balances
+            .get(account)
+            .cloned()
+            .unwrap_or_else(A::zero)
+            .checked_add(amount)
+            .ok_or_else(overflow)
+    }
+
+    // what is left of `balance` once `amount` is taken out of it
+    fn debited(balance: &A, amount: &A) -> Result<A, ErrorMsg> {
+        balance.checked_sub(amount)
+               .filter(|rest| *rest >= A::zero())
+               .ok_or("Balance is too low".to_string())
+    }
+
+    pub fn deposit(&mut self, depositor: String, amount: A) -> Result<Events<A>, ErrorMsg> {
+        if amount <= A::zero() {
             return Err("Amount should be greater than zero".to_string());
         }
 
-        let balance = self.balances.get(&withdrawer)
-                          .ok_or(format!("Could not find withdrawer {}", withdrawer))?;
+        let balance = self.credited(&depositor, &amount)?;
+        self.balances.insert(depositor.clone(), balance);
 
-        if balance < &amount {
-            return Err("Balance is too low".to_string());
+        Ok(vec![Event::BalanceCredited { account: depositor, amount }])
+    }
+
+    pub fn withdraw(&mut self, withdrawer: String, amount: A) -> Result<Events<A>, ErrorMsg> {
+        if amount <= A::zero() {
+            return Err("Amount should be greater than zero".to_string());
         }
 
-        self.balances
-            .entry(withdrawer)
-            .and_modify(|curr| *curr -= amount);
+        let balance = self.balances.get(&withdrawer)
+                          .ok_or(format!("Could not find withdrawer {withdrawer}"))?;
 
-        Ok(())
+        let balance = Self::debited(balance, &amount)?;
+        let window  = self.spend(&withdrawer, &amount)?;
+
+        self.balances.insert(withdrawer.clone(), balance);
+
+        let mut events = vec![Event::BalanceDebited { account: withdrawer.clone(), amount: amount.clone() }];
+        events.extend(self.record_spend(withdrawer, amount, window));
+        Ok(events)
     }
 
-    pub fn transfer(&mut self, sender: String, receiver: String, amount: BigInt) -> Result<(), ErrorMsg> {
-        if amount <= BigInt::from(0) {
+    pub fn transfer(&mut self, sender: String, receiver: String, amount: A) -> Result<Events<A>, ErrorMsg> {
+        if amount <= A::zero() {
             return Err("Amount should be greater than zero".to_string());
         }
 
         let balance = self.balances.get(&sender)
-                          .ok_or(format!("Could not find sender {}", sender))?;
+                          .ok_or(format!("Could not find sender {sender}"))?;
 
-        if balance < &amount {
-            return Err("Balance is too low".to_string());
-        }
+        let sender_balance = Self::debited(balance, &amount)?;
+        let window         = self.spend(&sender, &amount)?;
 
-        self
-            .balances
-            .entry(sender)
-            .and_modify(|curr| *curr -= amount.clone());
+        // computed before touching the state, so an overflow leaves it unchanged
+        let receiver_balance =
+            if receiver == sender {
+                sender_balance.checked_add(&amount).ok_or_else(overflow)?
+            }
+            else {
+                self.credited(&receiver, &amount)?
+            };
 
-        self
-            .balances
-            .entry(receiver)
-            .and_modify(|curr| *curr += amount.clone())
-            .or_insert(amount);
+        self.balances.insert(sender.clone(), sender_balance);
+        self.balances.insert(receiver.clone(), receiver_balance);
 
-        Ok(())
+        let mut events = vec![
+            Event::BalanceDebited { account: sender.clone(), amount: amount.clone() },
+            Event::BalanceCredited { account: receiver, amount: amount.clone() },
+        ];
+        events.extend(self.record_spend(sender, amount, window));
+        Ok(events)
     }
 
-    pub fn buy_investment(&mut self, buyer: String, amount: BigInt) -> Result<(), ErrorMsg> {
-        if amount <= BigInt::from(0) {
+    pub fn buy_investment(&mut self, buyer: String, amount: A) -> Result<Events<A>, ErrorMsg> {
+        if amount <= A::zero() {
             return Err("Amount should be greater than zero".to_string());
         }
 
         let balance = self.balances.get(&buyer)
-                          .ok_or(format!("Could not find buyer {}", buyer))?;
+                          .ok_or(format!("Could not find buyer {buyer}"))?;
 
-        if balance < &amount {
-            return Err("Balance is too low".to_string());
-        }
+        let balance = Self::debited(balance, &amount)?;
 
-        self
-            .balances
-            .entry(buyer.clone())
-            .and_modify(|curr| *curr -= amount.clone());
+        self.balances.insert(buyer.clone(), balance);
 
+        let id = self.next_id.clone();
         self.investments.insert(
-            self.next_id.clone(),
+            id.clone(),
             Investment {
-                owner: buyer,
-                amount,
+                owner:  buyer.clone(),
+                amount: amount.clone(),
             },
         );
 
         self.next_id += 1;
 
-        Ok(())
+        Ok(vec![
+            Event::BalanceDebited { account: buyer.clone(), amount: amount.clone() },
+            Event::InvestmentCreated { id, owner: buyer, amount },
+        ])
     }
 
-    pub fn sell_investment(&mut self, seller: String, investment_id: BigInt) -> Result<(), ErrorMsg> {
+    pub fn sell_investment(&mut self, seller: String, investment_id: BigInt) -> Result<Events<A>, ErrorMsg> {
         if let Some(investment) = self.investments.get(&investment_id) {
             if investment.owner != seller {
                 return Err("Seller can't sell an investment they don't own".to_string());
             }
-            self
-                .balances
-                .entry(seller)
-                .and_modify(|curr| *curr += investment.amount.clone());
+
+            let amount  = investment.amount.clone();
+            let balance = self.credited(&seller, &amount)?;
+            self.balances.insert(seller.clone(), balance);
 
             self.investments.remove(&investment_id);
 
-            Ok(())
+            Ok(vec![
+                Event::InvestmentClosed { id: investment_id, owner: seller.clone(), amount: amount.clone() },
+                Event::BalanceCredited { account: seller, amount },
+            ])
         }
         else {
             Err("No investment with this id".to_string())
         }
     }
 
+    pub fn allowance(&self, owner: &str, spender: &str) -> A {
+        self.allowances
+            .get(&(owner.to_string(), spender.to_string()))
+            .cloned()
+            .unwrap_or_else(A::zero)
+    }
+
+    pub fn approve(&mut self, owner: String, spender: String, amount: A) -> Result<Events<A>, ErrorMsg> {
+        if amount < A::zero() {
+            return Err("Allowance can't be negative".to_string());
+        }
+
+        self.allowances.insert((owner.clone(), spender.clone()), amount.clone());
+
+        Ok(vec![Event::AllowanceChanged { owner, spender, amount }])
+    }
+
+    pub fn transfer_from(&mut self, spender: String, owner: String, receiver: String, amount: A) -> Result<Events<A>, ErrorMsg> {
+        if amount <= A::zero() {
+            return Err("Amount should be greater than zero".to_string());
+        }
+
+        let allowance = self.allowance(&owner, &spender)
+                            .checked_sub(&amount)
+                            .filter(|rest| *rest >= A::zero())
+                            .ok_or("Allowance is too low".to_string())?;
+
+        let balance = self.balances.get(&owner)
+                          .ok_or(format!("Could not find owner {owner}"))?;
+
+        let owner_balance = Self::debited(balance, &amount)?;
+        let window        = self.spend(&owner, &amount)?;
+
+        // computed before touching the state, so an overflow leaves it unchanged
+        let receiver_balance =
+            if receiver == owner {
+                owner_balance.checked_add(&amount).ok_or_else(overflow)?
+            }
+            else {
+                self.credited(&receiver, &amount)?
+            };
+
+        self.balances.insert(owner.clone(), owner_balance);
+        self.balances.insert(receiver.clone(), receiver_balance);
+        self.allowances.insert((owner.clone(), spender.clone()), allowance.clone());
+
+        let mut events = vec![
+            Event::AllowanceChanged { owner: owner.clone(), spender, amount: allowance },
+            Event::BalanceDebited { account: owner.clone(), amount: amount.clone() },
+            Event::BalanceCredited { account: receiver, amount: amount.clone() },
+        ];
+        events.extend(self.record_spend(owner, amount, window));
+        Ok(events)
+    }
+
+    pub fn set_limit(&mut self, account: String, max: A, blocks: BigInt) -> Result<Events<A>, ErrorMsg> {
+        if max < A::zero() {
+            return Err("Limit can't be negative".to_string());
+        }
+        if blocks <= BigInt::from(0) {
+            return Err("Window should be at least one block".to_string());
+        }
+
+        self.limits.insert(account.clone(), RateLimit { max: max.clone(), blocks: blocks.clone() });
+
+        Ok(vec![Event::RateLimitSet { account, max, blocks }])
+    }
+
+    pub fn is_frozen(&self, account: &str) -> bool {
+        self.frozen.contains(account)
+    }
+
+    pub fn freeze(&mut self, account: String) -> Result<Events<A>, ErrorMsg> {
+        self.frozen.insert(account.clone());
+        Ok(vec![Event::AccountFrozen { account }])
+    }
+
+    pub fn unfreeze(&mut self, account: String) -> Result<Events<A>, ErrorMsg> {
+        self.frozen.remove(&account);
+        Ok(vec![Event::AccountUnfrozen { account }])
+    }
+
+    /// What left `account` within the window of its rate limit, oldest first.
+    pub fn window(&self, account: &str) -> Vector<Spend<A>> {
+        let (Some(limit), Some(spent)) = (self.limits.get(account), self.spent.get(account)) else {
+            return Vector::new();
+        };
+
+        let start = &self.height - &limit.blocks;
+        spent.iter().filter(|spend| spend.height > start).cloned().collect()
+    }
+
+    // the window of `account` once `amount` left it, expired spending being dropped
+    fn spent_after(&self, account: &str, amount: &A) -> Vector<Spend<A>> {
+        let mut window = self.window(account);
+        window.push_back(Spend { height: self.height.clone(), amount: amount.clone() });
+        window
+    }
+
+    // checks that `amount` may leave `account` now, returning its new window
+    // if it is rate limited, to be stored by `record_spend`
+    fn spend(&self, account: &str, amount: &A) -> Result<Option<Vector<Spend<A>>>, RateLimited<A>> {
+        let Some(limit) = self.limits.get(account) else {
+            return Ok(None);
+        };
+
+        let window = self.window(account);
+
+        // lowering a limit may leave more spent than it allows, and nothing available
+        let available = window.iter()
+            .try_fold(A::zero(), |used, spend| used.checked_add(&spend.amount))
+            .and_then(|used| limit.max.checked_sub(&used))
+            .filter(|available| *available >= A::zero())
+            .unwrap_or_else(A::zero);
+
+        if *amount > available {
+            let resets_at = (*amount <= limit.max).then(|| {
+                window.last().map_or_else(|| self.height.clone(), |spend| &spend.height + &limit.blocks)
+            });
+            return Err(RateLimited { account: account.to_string(), available, resets_at });
+        }
+
+        Ok(Some(self.spent_after(account, amount)))
+    }
+
+    fn record_spend(&mut self, account: String, amount: A, window: Option<Vector<Spend<A>>>) -> Option<Event<A>> {
+        self.spent.insert(account.clone(), window?);
+        Some(Event::RateLimitUsed { account, amount })
+    }
+
+    pub fn schedule_transfer(&mut self, sender: String, receiver: String, amount: A, at: BigInt, every: Option<BigInt>) -> Result<Events<A>, ErrorMsg> {
+        if amount <= A::zero() {
+            return Err("Amount should be greater than zero".to_string());
+        }
+        if at <= self.height {
+            return Err("Transfer should be scheduled in the future".to_string());
+        }
+        if every.as_ref().is_some_and(|period| period <= &BigInt::from(0)) {
+            return Err("Period should be greater than zero".to_string());
+        }
+
+        let id = self.next_schedule_id.clone();
+        self.schedules.insert(id.clone(), Schedule {
+            sender:   sender.clone(),
+            receiver: receiver.clone(),
+            amount:   amount.clone(),
+            at:       at.clone(),
+            every:    every.clone(),
+        });
+
+        self.next_schedule_id += 1;
+
+        Ok(vec![Event::TransferScheduled { id, sender, receiver, amount, at, every }])
+    }
+
+    /// Advances the height by one block and executes the transfers due by then.
+    ///
+    /// Due transfers run in the order they were scheduled. A transfer failing,
+    /// e.g. for lack of funds, does not abort the tick: the failure is recorded
+    /// in `failed_transfers` and the schedule moves on as if it had run.
+    pub fn tick(&mut self) -> Result<Events<A>, ErrorMsg> {
+        self.height += 1;
+        let mut events = vec![Event::HeightAdvanced { height: self.height.clone() }];
+
+        let due: Vec<BigInt> = self.schedules
+            .iter()
+            .filter(|(_, schedule)| schedule.at <= self.height)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in due {
+            let schedule = self.schedules[&id].clone();
+
+            // due transfers are not authorized by a caller, so frozen senders are checked here
+            let transfer =
+                if self.frozen.contains(&schedule.sender) {
+                    Err(format!("{} is frozen", schedule.sender))
+                }
+                else {
+                    self.transfer(schedule.sender, schedule.receiver, schedule.amount)
+                };
+
+            match transfer {
+                Ok(transfer) => events.extend(transfer),
+                Err(reason)  => {
+                    let height = self.height.clone();
+                    self.failed_transfers.push_back(FailedTransfer {
+                        id:     id.clone(),
+                        height: height.clone(),
+                        reason: reason.clone(),
+                    });
+                    events.push(Event::TransferFailed { id: id.clone(), height, reason });
+                }
+            }
+
+            match schedule.every {
+                Some(period) => {
+                    let at = schedule.at + period;
+                    self.schedules[&id].at = at.clone();
+                    events.push(Event::TransferRescheduled { id, at });
+                }
+                None => {
+                    self.schedules.remove(&id);
+                    events.push(Event::ScheduleEnded { id });
+                }
+            }
+        }
+
+        Ok(events)
+    }
 }
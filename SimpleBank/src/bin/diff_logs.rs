@@ -0,0 +1,46 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use clap::Parser;
+use simple_bank::log_diff::first_divergence;
+
+/// Report the first state on which two state logs disagree
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    left: PathBuf,
+    right: PathBuf,
+}
+
+fn open(path: &PathBuf) -> Result<BufReader<File>, String> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| format!("Could not open {}: {e}", path.display()))
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let result = open(&args.left).and_then(|left| {
+        open(&args.right).and_then(|right| first_divergence(left, right))
+    });
+
+    match result {
+        Ok(None) => {
+            println!("Logs are identical");
+            ExitCode::SUCCESS
+        }
+        Ok(Some(divergence)) => {
+            println!("Logs diverge at state {}:", divergence.line);
+            for difference in divergence.differences {
+                println!("  {difference}");
+            }
+            ExitCode::FAILURE
+        }
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::from(2)
+        }
+    }
+}
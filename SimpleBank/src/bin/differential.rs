@@ -0,0 +1,50 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::process::ExitCode;
+use clap::Parser;
+use simple_bank::differential::run;
+use simple_bank::explorer::Domains;
+
+/// Run random action sequences against both the bank and the reference model of bank.qnt
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Number of random sequences
+    #[clap(short, long, default_value_t = 1000)]
+    runs: u64,
+
+    /// Number of actions per sequence
+    #[clap(short, long, default_value_t = 100)]
+    steps: usize,
+
+    /// Seed of the first sequence, sequence `i` uses `seed + i`
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Also report actions failing with a different error message
+    #[clap(long)]
+    strict_errors: bool,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let domains = Domains::spec();
+
+    for seed in args.seed..args.seed + args.runs {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        if let Err(disagreement) = run(&mut rng, &domains, args.steps, args.strict_errors) {
+            println!("Disagreement with seed {seed} after {} actions: {}",
+                     disagreement.trace.len(), disagreement.reason);
+            for (i, action) in disagreement.trace.iter().enumerate() {
+                println!("  {}. {action}", i + 1);
+            }
+            println!("implementation: {:?}", disagreement.implementation);
+            println!("reference:      {:?}", disagreement.reference);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    println!("{} sequences of {} actions, no disagreement", args.runs, args.steps);
+    ExitCode::SUCCESS
+}
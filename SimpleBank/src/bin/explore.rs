@@ -0,0 +1,70 @@
+use num_bigint::BigInt;
+use std::process::ExitCode;
+use clap::Parser;
+use simple_bank::explorer::{explore, Domains, INVARIANTS};
+
+/// Explore every state of the bank reachable over small domains, checking invariants
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Maximum number of actions from the initial state
+    #[clap(short, long, default_value_t = 4)]
+    depth: usize,
+
+    /// Accounts the actions pick from
+    #[clap(long, value_delimiter = ',', default_value = "Alice,Bob,Charlie")]
+    accounts: Vec<String>,
+
+    /// Amounts the actions pick from
+    #[clap(long, value_delimiter = ',', allow_negative_numbers = true, default_value = "-1,0,1,2")]
+    amounts: Vec<BigInt>,
+
+    /// Investment ids the sell actions pick from
+    #[clap(long, value_delimiter = ',', default_value = "0,1")]
+    ids: Vec<BigInt>,
+
+    /// Heights transfers are scheduled at, relative to the current one
+    #[clap(long, value_delimiter = ',', allow_negative_numbers = true, default_value = "0,1")]
+    delays: Vec<BigInt>,
+
+    /// Periods of scheduled transfers, 0 for one-off transfers
+    #[clap(long, value_delimiter = ',', default_value = "0,1")]
+    periods: Vec<BigInt>,
+
+    /// Windows of rate limits, in blocks
+    #[clap(long, value_delimiter = ',', default_value = "1")]
+    windows: Vec<BigInt>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let domains = Domains {
+        accounts:       args.accounts,
+        amounts:        args.amounts,
+        investment_ids: args.ids,
+        delays:         args.delays,
+        periods:        args.periods,
+        windows:        args.windows,
+    };
+
+    let report = explore(&domains, args.depth, INVARIANTS);
+
+    println!("{} distinct states, {} transitions, depth {} reached",
+             report.states, report.transitions, report.depth);
+
+    match report.violation {
+        None => {
+            println!("No invariant violated");
+            ExitCode::SUCCESS
+        }
+        Some(violation) => {
+            println!("Invariant {} violated after {} actions: {}",
+                     violation.invariant, violation.trace.len(), violation.message);
+            for (i, action) in violation.trace.iter().enumerate() {
+                println!("  {}. {action}", i + 1);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use clap::Parser;
+use simple_bank::mbt::State;
+use simple_bank::regression::{render_test, test_name};
+use simple_bank::trace_reader::TraceReader;
+
+/// Generate a Rust regression test from an MBT trace
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// ITF trace exported by `quint run bank.qnt --mbt` (`-` for stdin)
+    trace: PathBuf,
+
+    /// Name of the generated test, derived from the trace file name by default
+    #[clap(short, long)]
+    name: Option<String>,
+
+    /// Directory of a test target (e.g. tests/regressions) to add the test to,
+    /// instead of printing it
+    #[clap(short, long)]
+    out_dir: Option<PathBuf>,
+}
+
+// writes `<dir>/<name>.rs` and declares it in `<dir>/main.rs`
+fn add_to_test_target(dir: &PathBuf, name: &str, test: &str) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(format!("{name}.rs")), test)?;
+
+    let main    = dir.join("main.rs");
+    let decl    = format!("mod {name};");
+    let current = fs::read_to_string(&main).unwrap_or_default();
+
+    if !current.lines().any(|line| line.trim() == decl) {
+        let mut file = OpenOptions::new().create(true).append(true).open(&main)?;
+        writeln!(file, "{decl}")?;
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let source = args.trace.file_name()
+                     .map(|s| s.to_string_lossy().into_owned())
+                     .unwrap_or("stdin".to_string());
+    let name   = args.name.unwrap_or_else(|| test_name(&source));
+
+    let result = TraceReader::<_, State>::open(&args.trace)
+        .map_err(|e| format!("Could not open {}: {e}", args.trace.display()))
+        .and_then(|trace| render_test(&name, &source, trace));
+
+    let test = match result {
+        Ok(test) => test,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match args.out_dir {
+        None => print!("{test}"),
+        Some(dir) => {
+            if let Err(err) = add_to_test_target(&dir, &name, &test) {
+                eprintln!("Error: {err}");
+                return ExitCode::FAILURE;
+            }
+            println!("Added {name} to {}", dir.display());
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,175 @@
+// Differential testing of `BankState` against the reference model of bank.qnt.
+//
+// Both start from their own initial state: an empty bank for the
+// implementation, every account at zero for the spec. States are compared up
+// to that difference, i.e. an account missing from the implementation is
+// equivalent to an account with a zero balance in the reference.
+
+use num_bigint::BigInt;
+use num_traits::Zero;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use crate::action::Action;
+use crate::bank;
+use crate::explorer::Domains;
+use crate::reference;
+
+#[derive(Clone, Debug)]
+pub struct Disagreement {
+    /// Actions from the initial states, the last one being where both disagree
+    pub trace:          Vec<Action>,
+    pub implementation: Result<(), String>,
+    pub reference:      Result<(), String>,
+    pub reason:         String,
+}
+
+impl Domains {
+    /// `ACCOUNTS`, `AMOUNTS`, and the other values picked by bank.qnt.
+    pub fn spec() -> Self {
+        Domains {
+            accounts:       vec!["Alice".into(), "Bob".into(), "Charlie".into()],
+            amounts:        (-50..=100).map(BigInt::from).collect(),
+            investment_ids: (0..=10).map(BigInt::from).collect(),
+            delays:         (-1..=3).map(BigInt::from).collect(),
+            periods:        (0..=3).map(BigInt::from).collect(),
+            windows:        (0..=3).map(BigInt::from).collect(),
+        }
+    }
+}
+
+/// Picks an action the way `step` does in bank.qnt, at height `height`.
+pub fn random_action(rng: &mut impl Rng, domains: &Domains, height: &BigInt) -> Action {
+    let account = |rng: &mut _| domains.accounts.choose(rng).unwrap().clone();
+    let amount  = |rng: &mut _| domains.amounts.choose(rng).unwrap().clone();
+
+    match rng.gen_range(0..10) {
+        0 => Action::Deposit { depositor: account(rng), amount: amount(rng) },
+        1 => Action::Withdraw { withdrawer: account(rng), amount: amount(rng) },
+        2 => Action::Transfer { sender: account(rng), receiver: account(rng), amount: amount(rng) },
+        3 => Action::BuyInvestment { buyer: account(rng), amount: amount(rng) },
+        4 => Action::SellInvestment {
+            seller:        account(rng),
+            investment_id: domains.investment_ids.choose(rng).unwrap().clone(),
+        },
+        5 => Action::Approve { owner: account(rng), spender: account(rng), amount: amount(rng) },
+        6 => Action::TransferFrom {
+            spender:  account(rng),
+            owner:    account(rng),
+            receiver: account(rng),
+            amount:   amount(rng),
+        },
+        7 => {
+            let period = domains.periods.choose(rng).unwrap();
+            Action::ScheduleTransfer {
+                sender:   account(rng),
+                receiver: account(rng),
+                amount:   amount(rng),
+                at:       height + domains.delays.choose(rng).unwrap(),
+                every:    (!period.is_zero()).then(|| period.clone()),
+            }
+        }
+        8 => Action::SetLimit {
+            account: account(rng),
+            max:     amount(rng),
+            blocks:  domains.windows.choose(rng).unwrap().clone(),
+        },
+        _ => Action::Tick,
+    }
+}
+
+/// Checks that both states agree, reporting the first difference otherwise.
+pub fn compare_states(implementation: &bank::BankState, reference: &reference::BankState) -> Result<(), String> {
+    let zero = BigInt::zero();
+
+    let accounts = implementation.balances.keys().chain(reference.balances.keys());
+    for account in accounts {
+        let ours   = implementation.balances.get(account).unwrap_or(&zero);
+        let theirs = reference.balances.get(account).unwrap_or(&zero);
+        if ours != theirs {
+            return Err(format!("balance of {account} is {ours} instead of {theirs}"));
+        }
+    }
+
+    let ours: Vec<_>   = implementation.investments.iter().collect();
+    let theirs: Vec<_> = reference.investments.iter().collect();
+    if ours != theirs {
+        return Err(format!("investments are {ours:?} instead of {theirs:?}"));
+    }
+
+    if implementation.next_id != reference.next_id {
+        return Err(format!("next_id is {} instead of {}", implementation.next_id, reference.next_id));
+    }
+
+    let ours: Vec<_>   = implementation.allowances.iter().collect();
+    let theirs: Vec<_> = reference.allowances.iter().collect();
+    if ours != theirs {
+        return Err(format!("allowances are {ours:?} instead of {theirs:?}"));
+    }
+
+    if implementation.height != reference.height {
+        return Err(format!("height is {} instead of {}", implementation.height, reference.height));
+    }
+
+    let ours: Vec<_>   = implementation.schedules.iter().collect();
+    let theirs: Vec<_> = reference.schedules.iter().collect();
+    if ours != theirs || implementation.next_schedule_id != reference.next_schedule_id {
+        return Err(format!("schedules are {ours:?} instead of {theirs:?}"));
+    }
+
+    // reasons may differ, e.g. for senders the implementation has never seen
+    let ours: Vec<_>   = implementation.failed_transfers.iter().map(|f| (&f.id, &f.height)).collect();
+    let theirs: Vec<_> = reference.failed_transfers.iter().map(|f| (&f.id, &f.height)).collect();
+    if ours != theirs {
+        return Err(format!("failed transfers are {ours:?} instead of {theirs:?}"));
+    }
+
+    let ours: Vec<_>   = implementation.limits.iter().collect();
+    let theirs: Vec<_> = reference.limits.iter().collect();
+    if ours != theirs {
+        return Err(format!("limits are {ours:?} instead of {theirs:?}"));
+    }
+
+    let ours: Vec<_>   = implementation.spent.iter().map(|(account, spent)| (account, spent.iter().collect::<Vec<_>>())).collect();
+    let theirs: Vec<_> = reference.spent.iter().map(|(account, spent)| (account, spent.iter().collect::<Vec<_>>())).collect();
+    if ours != theirs {
+        return Err(format!("spending is {ours:?} instead of {theirs:?}"));
+    }
+
+    Ok(())
+}
+
+/// Runs `steps` random actions against both, stopping at the first disagreement.
+///
+/// With `strict_errors`, failing actions must also fail with the same message.
+pub fn run(rng: &mut impl Rng, domains: &Domains, steps: usize, strict_errors: bool) -> Result<(), Disagreement> {
+    let mut implementation = bank::BankState::new();
+    let mut reference      = reference::init(&domains.accounts);
+    let mut trace          = Vec::new();
+
+    for _ in 0..steps {
+        let action = random_action(rng, domains, &implementation.height);
+        trace.push(action.clone());
+
+        let ours = implementation.apply(action.clone()).map(|_events| ());
+        let (theirs, next) =
+            match reference::apply(&reference, &action) {
+                Ok(next)   => (Ok(()), next),
+                Err(error) => (Err(error), reference.clone()),
+            };
+        reference = next;
+
+        let reason =
+            match (&ours, &theirs) {
+                (Ok(()), Err(_)) => Some("the implementation accepts an action the spec rejects".to_string()),
+                (Err(_), Ok(())) => Some("the implementation rejects an action the spec accepts".to_string()),
+                (Err(a), Err(b)) if strict_errors && a != b => Some("the errors differ".to_string()),
+                _ => compare_states(&implementation, &reference).err(),
+            };
+
+        if let Some(reason) = reason {
+            return Err(Disagreement { trace, implementation: ours, reference: theirs, reason });
+        }
+    }
+
+    Ok(())
+}
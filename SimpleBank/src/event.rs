@@ -0,0 +1,139 @@
+// Events emitted by `BankState` operations.
+//
+// Each successful operation returns the events describing what it changed,
+// in the order it changed it, so consumers can follow the bank without
+// diffing states. Like the state log, amounts are serialized as strings.
+
+use num_bigint::BigInt;
+use serde::{Serialize, Deserialize};
+use serde_with::DisplayFromStr;
+use serde_with_macros::serde_as;
+use std::fmt::{self, Display};
+use crate::amount::Amount;
+
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound(serialize = "A: Display", deserialize = "A: Amount"))]
+pub enum Event<A = BigInt> {
+    BalanceCredited {
+        account: String,
+        #[serde_as(as = "DisplayFromStr")]
+        amount:  A,
+    },
+    BalanceDebited {
+        account: String,
+        #[serde_as(as = "DisplayFromStr")]
+        amount:  A,
+    },
+    InvestmentCreated {
+        #[serde_as(as = "DisplayFromStr")]
+        id:     BigInt,
+        owner:  String,
+        #[serde_as(as = "DisplayFromStr")]
+        amount: A,
+    },
+    InvestmentClosed {
+        #[serde_as(as = "DisplayFromStr")]
+        id:     BigInt,
+        owner:  String,
+        #[serde_as(as = "DisplayFromStr")]
+        amount: A,
+    },
+    /// `amount` is the new allowance of `spender` on `owner`
+    AllowanceChanged {
+        owner:   String,
+        spender: String,
+        #[serde_as(as = "DisplayFromStr")]
+        amount:  A,
+    },
+    TransferScheduled {
+        #[serde_as(as = "DisplayFromStr")]
+        id:       BigInt,
+        sender:   String,
+        receiver: String,
+        #[serde_as(as = "DisplayFromStr")]
+        amount:   A,
+        #[serde_as(as = "DisplayFromStr")]
+        at:       BigInt,
+        #[serde_as(as = "Option<DisplayFromStr>")]
+        every:    Option<BigInt>,
+    },
+    HeightAdvanced {
+        #[serde_as(as = "DisplayFromStr")]
+        height: BigInt,
+    },
+    TransferFailed {
+        #[serde_as(as = "DisplayFromStr")]
+        id:     BigInt,
+        #[serde_as(as = "DisplayFromStr")]
+        height: BigInt,
+        reason: String,
+    },
+    /// Recurring transfer `id` is next due at `at`
+    TransferRescheduled {
+        #[serde_as(as = "DisplayFromStr")]
+        id: BigInt,
+        #[serde_as(as = "DisplayFromStr")]
+        at: BigInt,
+    },
+    ScheduleEnded {
+        #[serde_as(as = "DisplayFromStr")]
+        id: BigInt,
+    },
+    RateLimitSet {
+        account: String,
+        #[serde_as(as = "DisplayFromStr")]
+        max:     A,
+        #[serde_as(as = "DisplayFromStr")]
+        blocks:  BigInt,
+    },
+    /// `amount` left rate-limited `account`, at the current height
+    RateLimitUsed {
+        account: String,
+        #[serde_as(as = "DisplayFromStr")]
+        amount:  A,
+    },
+    AccountFrozen {
+        account: String,
+    },
+    AccountUnfrozen {
+        account: String,
+    },
+}
+
+impl<A: Display> Display for Event<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::BalanceCredited { account, amount } =>
+                write!(f, "{account} credited {amount}"),
+            Event::BalanceDebited { account, amount } =>
+                write!(f, "{account} debited {amount}"),
+            Event::InvestmentCreated { id, owner, amount } =>
+                write!(f, "investment {id} of {amount} created for {owner}"),
+            Event::InvestmentClosed { id, owner, amount } =>
+                write!(f, "investment {id} of {amount} closed by {owner}"),
+            Event::AllowanceChanged { owner, spender, amount } =>
+                write!(f, "{spender} may now spend {amount} of {owner}"),
+            Event::TransferScheduled { id, sender, receiver, amount, at, every: None } =>
+                write!(f, "transfer {id} of {amount} from {sender} to {receiver} scheduled at {at}"),
+            Event::TransferScheduled { id, sender, receiver, amount, at, every: Some(period) } =>
+                write!(f, "transfer {id} of {amount} from {sender} to {receiver} scheduled at {at}, then every {period}"),
+            Event::HeightAdvanced { height } =>
+                write!(f, "height is now {height}"),
+            Event::TransferFailed { id, height, reason } =>
+                write!(f, "transfer {id} failed at {height}: {reason}"),
+            Event::TransferRescheduled { id, at } =>
+                write!(f, "transfer {id} next due at {at}"),
+            Event::ScheduleEnded { id } =>
+                write!(f, "transfer {id} ended"),
+            Event::RateLimitSet { account, max, blocks } =>
+                write!(f, "{account} may now move at most {max} per {blocks} blocks"),
+            Event::RateLimitUsed { account, amount } =>
+                write!(f, "{amount} counted against the rate limit of {account}"),
+            Event::AccountFrozen { account } =>
+                write!(f, "{account} frozen"),
+            Event::AccountUnfrozen { account } =>
+                write!(f, "{account} unfrozen"),
+        }
+    }
+}
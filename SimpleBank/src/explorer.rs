@@ -0,0 +1,241 @@
+// Bounded breadth-first exploration of the Rust `BankState`.
+//
+// Unlike the MBT traces, which check the implementation against behaviours
+// picked by Quint, this enumerates every action over small domains and runs
+// them on the implementation itself. States are deduplicated, so each
+// reachable state is expanded once, and counterexamples are shortest since
+// states are visited in order of depth.
+
+use num_bigint::BigInt;
+use num_traits::Zero;
+use std::collections::{HashSet, VecDeque};
+use crate::action::Action;
+use crate::bank::BankState;
+
+/// Values the explorer picks action arguments from.
+#[derive(Clone, Debug)]
+pub struct Domains {
+    pub accounts:       Vec<String>,
+    pub amounts:        Vec<BigInt>,
+    pub investment_ids: Vec<BigInt>,
+    /// Heights transfers are scheduled at, relative to the current one
+    pub delays:         Vec<BigInt>,
+    /// Periods of scheduled transfers, 0 for one-off transfers
+    pub periods:        Vec<BigInt>,
+    /// Windows of rate limits, in blocks
+    pub windows:        Vec<BigInt>,
+}
+
+impl Default for Domains {
+    fn default() -> Self {
+        Domains {
+            accounts:       vec!["Alice".into(), "Bob".into(), "Charlie".into()],
+            amounts:        vec![BigInt::from(-1), BigInt::from(0), BigInt::from(1), BigInt::from(2)],
+            investment_ids: vec![BigInt::from(0), BigInt::from(1)],
+            delays:         vec![BigInt::from(0), BigInt::from(1)],
+            periods:        vec![BigInt::from(0), BigInt::from(1)],
+            windows:        vec![BigInt::from(1)],
+        }
+    }
+}
+
+impl Domains {
+    /// Every action over the domains, at height `height`.
+    pub fn actions(&self, height: &BigInt) -> Vec<Action> {
+        let mut actions = vec![Action::Tick];
+
+        for account in &self.accounts {
+            for amount in &self.amounts {
+                actions.push(Action::Deposit { depositor: account.clone(), amount: amount.clone() });
+                actions.push(Action::Withdraw { withdrawer: account.clone(), amount: amount.clone() });
+                actions.push(Action::BuyInvestment { buyer: account.clone(), amount: amount.clone() });
+
+                for blocks in &self.windows {
+                    actions.push(Action::SetLimit {
+                        account: account.clone(),
+                        max:     amount.clone(),
+                        blocks:  blocks.clone(),
+                    });
+                }
+
+                for other in &self.accounts {
+                    actions.push(Action::Transfer {
+                        sender:   account.clone(),
+                        receiver: other.clone(),
+                        amount:   amount.clone(),
+                    });
+                    actions.push(Action::Approve {
+                        owner:   account.clone(),
+                        spender: other.clone(),
+                        amount:  amount.clone(),
+                    });
+
+                    for delay in &self.delays {
+                        for period in &self.periods {
+                            actions.push(Action::ScheduleTransfer {
+                                sender:   account.clone(),
+                                receiver: other.clone(),
+                                amount:   amount.clone(),
+                                at:       height + delay,
+                                every:    (!period.is_zero()).then(|| period.clone()),
+                            });
+                        }
+                    }
+
+                    for receiver in &self.accounts {
+                        actions.push(Action::TransferFrom {
+                            spender:  other.clone(),
+                            owner:    account.clone(),
+                            receiver: receiver.clone(),
+                            amount:   amount.clone(),
+                        });
+                    }
+                }
+            }
+
+            for id in &self.investment_ids {
+                actions.push(Action::SellInvestment { seller: account.clone(), investment_id: id.clone() });
+            }
+        }
+
+        actions
+    }
+}
+
+/// A reachable state, along with the net amount deposited to reach it.
+///
+/// `supply` is a ghost variable: it is not part of the bank, but it is
+/// what conservation of money is checked against.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Node {
+    pub state:  BankState,
+    pub supply: BigInt,
+}
+
+pub type Invariant = fn(&Node) -> Result<(), String>;
+
+pub fn no_negative_balances(node: &Node) -> Result<(), String> {
+    for (account, balance) in &node.state.balances {
+        if balance < &BigInt::zero() {
+            return Err(format!("{account} has a negative balance {balance}"));
+        }
+    }
+    for (id, investment) in &node.state.investments {
+        if investment.amount <= BigInt::zero() {
+            return Err(format!("investment {id} has a non-positive amount {}", investment.amount));
+        }
+    }
+    for ((owner, spender), allowance) in &node.state.allowances {
+        if allowance < &BigInt::zero() {
+            return Err(format!("{spender} has a negative allowance {allowance} on {owner}"));
+        }
+    }
+    Ok(())
+}
+
+pub fn conservation(node: &Node) -> Result<(), String> {
+    let balances: BigInt    = node.state.balances.values().sum();
+    let investments: BigInt = node.state.investments.values().map(|i| &i.amount).sum();
+
+    if &balances + &investments != node.supply {
+        return Err(format!(
+            "balances ({balances}) and investments ({investments}) do not add up to the net deposits ({})",
+            node.supply
+        ));
+    }
+    Ok(())
+}
+
+pub const INVARIANTS: &[(&str, Invariant)] = &[
+    ("no_negative_balances", no_negative_balances),
+    ("conservation", conservation),
+];
+
+#[derive(Clone, Debug)]
+pub struct Violation {
+    pub invariant: &'static str,
+    pub message:   String,
+    /// Actions leading from `BankState::new()` to the violating state
+    pub trace:     Vec<Action>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Report {
+    /// Number of distinct states reached
+    pub states:      usize,
+    /// Number of successful actions tried, including those leading to known states
+    pub transitions: usize,
+    /// Depth of the deepest new state
+    pub depth:       usize,
+    pub violation:   Option<Violation>,
+}
+
+fn supply_after(supply: &BigInt, action: &Action) -> BigInt {
+    match action {
+        Action::Deposit { amount, .. }  => supply + amount,
+        Action::Withdraw { amount, .. } => supply - amount,
+        _                               => supply.clone(),
+    }
+}
+
+/// Explores every state reachable from `BankState::new()` in at most `max_depth` actions.
+pub fn explore(domains: &Domains, max_depth: usize, invariants: &[(&'static str, Invariant)]) -> Report {
+    // every state seen, with the index of its predecessor and the action leading to it
+    let mut nodes: Vec<(Node, Option<(usize, Action)>)> = Vec::new();
+    let mut seen: HashSet<Node> = HashSet::new();
+    let mut frontier: VecDeque<(usize, usize)> = VecDeque::new();
+
+    let mut report = Report { states: 0, transitions: 0, depth: 0, violation: None };
+
+    let trace_to = |nodes: &Vec<(Node, Option<(usize, Action)>)>, mut index: usize| {
+        let mut trace = Vec::new();
+        while let Some((parent, action)) = &nodes[index].1 {
+            trace.push(action.clone());
+            index = *parent;
+        }
+        trace.reverse();
+        trace
+    };
+
+    let init = Node { state: BankState::new(), supply: BigInt::zero() };
+    seen.insert(init.clone());
+    nodes.push((init, None));
+    frontier.push_back((0, 0));
+
+    while let Some((index, depth)) = frontier.pop_front() {
+        for (name, invariant) in invariants {
+            if let Err(message) = invariant(&nodes[index].0) {
+                report.states    = nodes.len();
+                report.violation = Some(Violation { invariant: name, message, trace: trace_to(&nodes, index) });
+                return report;
+            }
+        }
+
+        report.depth = report.depth.max(depth);
+        if depth == max_depth {
+            continue;
+        }
+
+        for action in &domains.actions(&nodes[index].0.state.height) {
+            let node      = &nodes[index].0;
+            // O(1), the maps are shared until the action modifies them
+            let mut state = node.state.clone();
+
+            if state.apply(action.clone()).is_err() {
+                continue;
+            }
+            report.transitions += 1;
+
+            let next = Node { state, supply: supply_after(&node.supply, action) };
+            if !seen.insert(next.clone()) {
+                continue;
+            }
+
+            frontier.push_back((nodes.len(), depth + 1));
+            nodes.push((next, Some((index, action.clone()))));
+        }
+    }
+
+    report.states = nodes.len();
+    report
+}
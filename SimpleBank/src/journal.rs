@@ -0,0 +1,115 @@
+// Journal of everything that happened to a `BankState`.
+//
+// Each entry is an action along with the events it emitted. The events alone
+// are enough to rebuild the state, while the actions give them context, e.g.
+// in account statements. Serialized one entry per line, it is the action log
+// of the REPL.
+
+use num_bigint::BigInt;
+use serde::{Serialize, Deserialize};
+use std::fmt::Display;
+use std::io::BufRead;
+use crate::action::Action;
+use crate::amount::Amount;
+use crate::bank::{BankState, ErrorMsg, Events};
+use crate::projection::Projection;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(serialize = "A: Display", deserialize = "A: Amount"))]
+pub struct Entry<A = BigInt> {
+    pub action: Action,
+    pub events: Events<A>,
+}
+
+/// Successful actions, in the order they were applied to a fresh `BankState`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Journal<A = BigInt> {
+    entries: Vec<Entry<A>>,
+}
+
+impl<A: Amount> Default for Journal<A> {
+    fn default() -> Self {
+        Journal { entries: Vec::new() }
+    }
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<A: Amount> Journal<A> {
+    /// Reads a journal written one entry per line, e.g. an action log.
+    pub fn read(input: impl BufRead) -> Result<Self, ErrorMsg> {
+        let mut journal = Self::default();
+
+        for (i, line) in input.lines().enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = serde_json::from_str(&line).map_err(|e| format!("line {}: {e}", i + 1))?;
+            journal.entries.push(entry);
+        }
+
+        Ok(journal)
+    }
+
+    /// Rebuilds a journal from an action log written before the journal, one
+    /// bare action per line, applying each action to a fresh `BankState` again.
+    pub fn from_actions(input: impl BufRead) -> Result<Self, ErrorMsg> {
+        let mut journal = Self::default();
+        let mut state   = BankState::default();
+
+        for (i, line) in input.lines().enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let action: Action = serde_json::from_str(&line).map_err(|e| format!("line {}: {e}", i + 1))?;
+            let events         = state.apply(action.clone()).map_err(|e| format!("line {}: {e}", i + 1))?;
+            journal.record(action, events);
+        }
+
+        Ok(journal)
+    }
+
+    pub fn record(&mut self, action: Action, events: Events<A>) -> &Entry<A> {
+        self.entries.push(Entry { action, events });
+        self.entries.last().unwrap()
+    }
+
+    pub fn entries(&self) -> &[Entry<A>] {
+        &self.entries
+    }
+
+    /// Feeds every event to `projection`, along with the index of its entry,
+    /// up to the first one it fails on.
+    pub fn project<P: Projection<A>>(&self, projection: &mut P) -> Result<(), ErrorMsg> {
+        for (seq, entry) in self.entries.iter().enumerate() {
+            for event in &entry.events {
+                projection.apply(seq, &entry.action, event)
+                          .map_err(|e| format!("Entry {seq} ({}): {e}", entry.action))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// State reached by replaying the events on a fresh `BankState`.
+    pub fn rebuild(&self) -> Result<BankState<A>, ErrorMsg> {
+        let mut state = BankState::default();
+
+        for (seq, entry) in self.entries.iter().enumerate() {
+            for event in &entry.events {
+                state.apply_event(event)
+                     .map_err(|e| format!("Entry {seq} ({}): {e}", entry.action))?;
+            }
+        }
+
+        Ok(state)
+    }
+}
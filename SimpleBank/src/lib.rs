@@ -1 +1,15 @@
+pub mod action;
+pub mod amount;
+pub mod auth;
 pub mod bank;
+pub mod differential;
+pub mod event;
+pub mod explorer;
+pub mod journal;
+pub mod log_diff;
+pub mod mbt;
+pub mod projection;
+pub mod reference;
+pub mod regression;
+pub mod service;
+pub mod trace_reader;
@@ -0,0 +1,108 @@
+// Semantic comparison of the JSON-lines logs written by the REPL.
+//
+// Logs are compared state by state (line by line), and the first pair of
+// states that differ is reported field by field, e.g. `balances.Alice`,
+// rather than as a textual diff of the whole line.
+
+use serde_json::Value;
+use std::fmt;
+use std::io::BufRead;
+
+#[derive(Debug, PartialEq)]
+pub struct Difference {
+    pub path:  String,
+    pub left:  Option<Value>,
+    pub right: Option<Value>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let show = |v: &Option<Value>| v.as_ref().map_or("<missing>".to_string(), Value::to_string);
+        write!(f, "{}: {} != {}", self.path, show(&self.left), show(&self.right))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Divergence {
+    /// 1-based line number, i.e. index of the state in both logs
+    pub line:        usize,
+    pub differences: Vec<Difference>,
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() { key.to_string() } else { format!("{path}.{key}") }
+}
+
+/// Appends to `out` every leaf where `left` and `right` differ.
+pub fn diff_values(path: &str, left: &Value, right: &Value, out: &mut Vec<Difference>) {
+    match (left, right) {
+        (Value::Object(l), Value::Object(r)) => {
+            for (key, lv) in l {
+                match r.get(key) {
+                    Some(rv) => diff_values(&join(path, key), lv, rv, out),
+                    None     => out.push(Difference {
+                        path:  join(path, key),
+                        left:  Some(lv.clone()),
+                        right: None,
+                    }),
+                }
+            }
+            for (key, rv) in r {
+                if !l.contains_key(key) {
+                    out.push(Difference {
+                        path:  join(path, key),
+                        left:  None,
+                        right: Some(rv.clone()),
+                    });
+                }
+            }
+        }
+        (Value::Array(l), Value::Array(r)) if l.len() == r.len() => {
+            for (i, (lv, rv)) in l.iter().zip(r).enumerate() {
+                diff_values(&join(path, &i.to_string()), lv, rv, out);
+            }
+        }
+        _ if left != right => out.push(Difference {
+            path:  if path.is_empty() { "<state>".to_string() } else { path.to_string() },
+            left:  Some(left.clone()),
+            right: Some(right.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn parse(line: Option<std::io::Result<String>>, index: usize) -> Result<Option<Value>, String> {
+    line.map(|line| {
+        let line = line.map_err(|e| e.to_string())?;
+        serde_json::from_str(&line).map_err(|e| format!("Line {index}: {e}"))
+    })
+    .transpose()
+}
+
+/// Finds the first state on which two logs disagree, reading them in lockstep.
+pub fn first_divergence(left: impl BufRead, right: impl BufRead) -> Result<Option<Divergence>, String> {
+    let mut left  = left.lines();
+    let mut right = right.lines();
+
+    for line in 1.. {
+        let l = parse(left.next(), line)?;
+        let r = parse(right.next(), line)?;
+
+        let mut differences = Vec::new();
+        match (&l, &r) {
+            (None, None)         => return Ok(None),
+            (Some(l), Some(r))   => diff_values("", l, r, &mut differences),
+            _ => differences.push(Difference {
+                path:  "<state>".to_string(),
+                left:  l.clone(),
+                right: r.clone(),
+            }),
+        }
+
+        if !differences.is_empty() {
+            return Ok(Some(Divergence { line, differences }));
+        }
+    }
+
+    unreachable!()
+}
@@ -1,5 +1,6 @@
 #![allow(unused_variables)]
 
+use std::fmt;
 use std::str::FromStr;
 use std::path::{PathBuf};
 use std::fs::{File, OpenOptions};
@@ -7,13 +8,15 @@ use std::io::Write;
 use rustyline::error::ReadlineError;
 use rustyline::{DefaultEditor};
 use serde_json::{to_string};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
-mod bank;
-mod action;
+mod rpc;
 
-use bank::{BankState, ErrorMsg};
-use action::Action;
+use simple_bank::bank::{BankState, ErrorMsg, Events};
+use simple_bank::action::Action;
+use simple_bank::auth::{Permissions, Unauthorized};
+use simple_bank::journal::{Entry, Journal};
+use simple_bank::projection::{Projection, Statements};
 
 // CLI arguments
 #[derive(Parser, Debug)]
@@ -26,12 +29,65 @@ struct Args {
     /// Log file to dump the state AND action trace
     #[clap(short, long)]
     action_log_file: Option<PathBuf>,
+
+    /// Account allowed to freeze other accounts, can be repeated
+    #[clap(long = "admin")]
+    admins: Vec<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+enum Command {
+    /// Serve the bank over line-delimited JSON-RPC instead of running the REPL
+    Serve {
+        /// Address to listen on
+        #[clap(long, default_value = "127.0.0.1:7878")]
+        addr: String,
+
+        /// Token an account must give to log in, as ACCOUNT=TOKEN, can be repeated;
+        /// accounts without one can't log in remotely
+        #[clap(long = "token", value_parser = parse_credential)]
+        tokens: Vec<(String, String)>,
+    },
+}
+
+fn parse_credential(arg: &str) -> Result<(String, String), ErrorMsg> {
+    match arg.split_once('=') {
+        Some((account, token)) if !account.is_empty() && !token.is_empty() =>
+            Ok((account.to_string(), token.to_string())),
+        _ => Err("expected ACCOUNT=TOKEN".to_string()),
+    }
 }
 
 struct BankApp {
-    state:      BankState,
-    state_log:  Option<File>,
-    action_log: Option<File>,
+    state:       BankState,
+    journal:     Journal,
+    // kept up to date with the journal, rather than replaying it for each statement
+    statements:  Statements,
+    permissions: Permissions,
+    state_log:   Option<File>,
+    action_log:  Option<File>,
+}
+
+#[derive(Debug)]
+enum ActionError {
+    Unauthorized(Unauthorized),
+    Bank(ErrorMsg),
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionError::Unauthorized(e) => write!(f, "{e}"),
+            ActionError::Bank(e)         => write!(f, "{e}"),
+        }
+    }
+}
+
+fn logged_in(caller: Option<&str>) -> Result<&str, Unauthorized> {
+    caller.ok_or(Unauthorized::NotLoggedIn)
 }
 
 fn setup_logfile(buf: Option<PathBuf>) -> Option<File> {
@@ -44,13 +100,51 @@ fn setup_logfile(buf: Option<PathBuf>) -> Option<File> {
     })
 }
 
+// REPL commands which are not bank actions
+enum SessionCommand {
+    Login(String),
+    Logout,
+    Statement(String),
+}
+
+impl SessionCommand {
+    // `None` if the line is not a session command, so it is parsed as an action
+    fn parse(line: &str) -> Option<Result<Self, ErrorMsg>> {
+        let mut parts = line.split_whitespace();
+        let cmd  = parts.next()?;
+        let args: Vec<&str> = parts.collect();
+
+        let arity = |n: usize| {
+            if args.len() < n {
+                Err("Too few arguments".to_string())
+            }
+            else if args.len() > n {
+                Err("Too many arguments".to_string())
+            }
+            else {
+                Ok(())
+            }
+        };
+
+        Some(match cmd {
+            "login"     => arity(1).map(|()| SessionCommand::Login(args[0].to_string())),
+            "logout"    => arity(0).map(|()| SessionCommand::Logout),
+            "statement" => arity(1).map(|()| SessionCommand::Statement(args[0].to_string())),
+            _ => return None,
+        })
+    }
+}
+
 impl BankApp {
     pub fn new(args: Args) -> Self {
         let state_log  = setup_logfile(args.state_log_file);
         let action_log = setup_logfile(args.action_log_file);
 
         BankApp {
-            state: BankState::new(),
+            state:       BankState::new(),
+            journal:     Journal::new(),
+            statements:  Statements::default(),
+            permissions: Permissions::new(args.admins),
             state_log,
             action_log,
         }
@@ -62,30 +156,70 @@ impl BankApp {
         };
     }
 
-    pub fn log_action(&mut self, action: Action) {
-        if let Some(ref mut log) = self.action_log {
-            writeln!(log, "{}", to_string(&action).unwrap()).unwrap();
+    // the action log is the journal, one entry per line
+    pub fn log_action(log: &mut Option<File>, entry: &Entry) {
+        if let Some(ref mut log) = log {
+            writeln!(log, "{}", to_string(entry).unwrap()).unwrap();
         };
     }
 
-    pub fn apply_action(&mut self, action: Action) -> Result<(), ErrorMsg> {
-        let result =
-            match action.clone() {
-                Action::Deposit { depositor, amount }            => self.state.deposit(depositor, amount),
-                Action::Withdraw { withdrawer, amount }          => self.state.withdraw(withdrawer, amount),
-                Action::Transfer { sender, receiver, amount }    => self.state.transfer(sender, receiver, amount),
-                Action::BuyInvestment { buyer, amount }          => self.state.buy_investment(buyer, amount),
-                Action::SellInvestment { seller, investment_id } => self.state.sell_investment(seller, investment_id),
-            }?;
+    /// Applies `action` on behalf of `caller`, if they are allowed to.
+    pub fn apply_action(&mut self, caller: Option<&str>, action: Action) -> Result<Events, ActionError> {
+        logged_in(caller)
+            .and_then(|caller| self.permissions.authorize(&self.state, caller, &action))
+            .map_err(ActionError::Unauthorized)?;
+
+        let events = self.state.apply(action.clone()).map_err(ActionError::Bank)?;
 
         self.log_state();
-        self.log_action(action);
+        let seq   = self.journal.entries().len();
+        let entry = self.journal.record(action, events);
+        Self::log_action(&mut self.action_log, entry);
+
+        for event in &entry.events {
+            self.statements.apply(seq, &entry.action, event).map_err(ActionError::Bank)?;
+        }
+
+        Ok(entry.events.clone())
+    }
+
+    pub fn print_statement(&self, account: &str) {
+        let lines = self.statements.statement(account);
+        if lines.is_empty() {
+            println!("No movement on {account}");
+        }
+        for line in lines {
+            println!("{line}");
+        }
+    }
+
+    fn run_session_command(&mut self, caller: &mut Option<String>, command: SessionCommand) {
+        let result: Result<(), ErrorMsg> =
+            match command {
+                // whoever runs the REPL already owns the bank, so logins are not checked
+                SessionCommand::Login(account) => {
+                    println!("Logged in as {account}");
+                    *caller = Some(account);
+                    Ok(())
+                }
+                SessionCommand::Logout => {
+                    *caller = None;
+                    Ok(())
+                }
+                SessionCommand::Statement(account) => {
+                    self.print_statement(&account);
+                    Ok(())
+                }
+            };
 
-        Ok(result)
+        if let Err(err) = result {
+            println!("Error: {err}");
+        }
     }
 
     pub fn repl(&mut self) -> rustyline::Result<()> {
         let mut rl = DefaultEditor::new()?;
+        let mut caller: Option<String> = None;
 
         self.log_state();
 
@@ -93,20 +227,32 @@ impl BankApp {
             let readline = rl.readline("> ");
             match readline {
                 Ok(line) => {
+                    if let Some(command) = SessionCommand::parse(&line) {
+                        rl.add_history_entry(&line)?;
+                        match command {
+                            Ok(command) => self.run_session_command(&mut caller, command),
+                            Err(err)    => println!("Error: {err}"),
+                        }
+                        continue;
+                    }
+
                     match Action::from_str(&line) {
                         Ok(action) => {
                             rl.add_history_entry(&line)?;
-                            match self.apply_action(action) {
-                                Ok(()) => {
+                            match self.apply_action(caller.as_deref(), action) {
+                                Ok(events) => {
+                                    for event in events {
+                                        println!("{event}");
+                                    }
                                     println!("{:?}", self.state);
                                 }
                                 Err(err) => {
-                                    println!("Could not apply action: {}", err);
+                                    println!("Could not apply action: {err}");
                                 }
                             };
                         },
                         Err(err) => {
-                            println!("Error: {}", err);
+                            println!("Error: {err}");
                         },
                     }
                 },
@@ -115,7 +261,7 @@ impl BankApp {
                     break
                 },
                 Err(err) => {
-                    println!("Error: {:?}", err);
+                    println!("Error: {err:?}");
                     return Err(err);
                 }
             }
@@ -126,7 +272,12 @@ impl BankApp {
 }
 
 fn main() -> rustyline::Result<()> {
-    let args = Args::parse();
+    let args    = Args::parse();
+    let command = args.command.clone();
     let mut app = BankApp::new(args);
-    app.repl()
+
+    match command {
+        None => app.repl(),
+        Some(Command::Serve { addr, tokens }) => Ok(rpc::serve(app, &addr, tokens.into_iter().collect())?),
+    }
 }
@@ -0,0 +1,137 @@
+// States of the ITF traces exported by `quint run bank.qnt --mbt`.
+
+use itf::de::{self, As};
+use num_bigint::BigInt;
+use serde::Deserialize;
+use crate::action::Action;
+use crate::amount::Amount;
+use crate::bank::BankState;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NondetPicks {
+    #[serde(with = "As::<de::Option::<_>>")]
+    pub depositor: Option<String>,
+
+    #[serde(with = "As::<de::Option::<_>>")]
+    pub withdrawer: Option<String>,
+
+    #[serde(with = "As::<de::Option::<_>>")]
+    pub sender: Option<String>,
+
+    #[serde(with = "As::<de::Option::<_>>")]
+    pub receiver: Option<String>,
+
+    #[serde(with = "As::<de::Option::<_>>")]
+    pub amount: Option<BigInt>,
+
+    #[serde(with = "As::<de::Option::<_>>")]
+    pub buyer: Option<String>,
+
+    #[serde(with = "As::<de::Option::<_>>")]
+    pub seller: Option<String>,
+
+    #[serde(with = "As::<de::Option::<_>>")]
+    pub id: Option<BigInt>,
+
+    // absent from traces of the spec before allowances
+    #[serde(default, with = "As::<de::Option::<_>>")]
+    pub owner: Option<String>,
+
+    #[serde(default, with = "As::<de::Option::<_>>")]
+    pub spender: Option<String>,
+
+    #[serde(default, with = "As::<de::Option::<_>>")]
+    pub at: Option<BigInt>,
+
+    // period of a scheduled transfer, 0 for a one-off one
+    #[serde(default, with = "As::<de::Option::<_>>")]
+    pub period: Option<BigInt>,
+
+    #[serde(default, with = "As::<de::Option::<_>>")]
+    pub account: Option<String>,
+
+    #[serde(default, with = "As::<de::Option::<_>>")]
+    pub max: Option<BigInt>,
+
+    #[serde(default, with = "As::<de::Option::<_>>")]
+    pub blocks: Option<BigInt>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(bound(deserialize = "A: Amount"))]
+pub struct State<A = BigInt> {
+    pub bank_state: BankState<A>,
+    #[serde(with = "As::<de::Option::<_>>")]
+    pub error: Option<String>,
+    #[serde(rename = "mbt::actionTaken")]
+    pub action_taken: String,
+    #[serde(rename = "mbt::nondetPicks")]
+    pub nondet_picks: NondetPicks,
+}
+
+fn pick<T: Clone>(pick: &Option<T>, name: &str) -> Result<T, String> {
+    pick.clone().ok_or(format!("Missing nondet pick {name}"))
+}
+
+impl<A> State<A> {
+    /// The action taken to reach this state, `None` for the initial state.
+    pub fn action(&self) -> Result<Option<Action>, String> {
+        let picks = &self.nondet_picks;
+
+        let action =
+            match self.action_taken.as_str() {
+                "init" => return Ok(None),
+                "deposit_action" => Action::Deposit {
+                    depositor: pick(&picks.depositor, "depositor")?,
+                    amount:    pick(&picks.amount, "amount")?,
+                },
+                "withdraw_action" => Action::Withdraw {
+                    withdrawer: pick(&picks.withdrawer, "withdrawer")?,
+                    amount:     pick(&picks.amount, "amount")?,
+                },
+                "transfer_action" => Action::Transfer {
+                    sender:   pick(&picks.sender, "sender")?,
+                    receiver: pick(&picks.receiver, "receiver")?,
+                    amount:   pick(&picks.amount, "amount")?,
+                },
+                "buy_investment_action" => Action::BuyInvestment {
+                    buyer:  pick(&picks.buyer, "buyer")?,
+                    amount: pick(&picks.amount, "amount")?,
+                },
+                "sell_investment_action" => Action::SellInvestment {
+                    seller:        pick(&picks.seller, "seller")?,
+                    investment_id: pick(&picks.id, "id")?,
+                },
+                "approve_action" => Action::Approve {
+                    owner:   pick(&picks.owner, "owner")?,
+                    spender: pick(&picks.spender, "spender")?,
+                    amount:  pick(&picks.amount, "amount")?,
+                },
+                "transfer_from_action" => Action::TransferFrom {
+                    spender:  pick(&picks.spender, "spender")?,
+                    owner:    pick(&picks.owner, "owner")?,
+                    receiver: pick(&picks.receiver, "receiver")?,
+                    amount:   pick(&picks.amount, "amount")?,
+                },
+                "schedule_transfer_action" => {
+                    let period = pick(&picks.period, "period")?;
+                    Action::ScheduleTransfer {
+                        sender:   pick(&picks.sender, "sender")?,
+                        receiver: pick(&picks.receiver, "receiver")?,
+                        amount:   pick(&picks.amount, "amount")?,
+                        at:       pick(&picks.at, "at")?,
+                        every:    (period != BigInt::from(0)).then_some(period),
+                    }
+                },
+                "tick_action" => Action::Tick,
+                "set_limit_action" => Action::SetLimit {
+                    account: pick(&picks.account, "account")?,
+                    max:     pick(&picks.max, "max")?,
+                    blocks:  pick(&picks.blocks, "blocks")?,
+                },
+                action => return Err(format!("Invalid action taken {action}")),
+            };
+
+        Ok(Some(action))
+    }
+}
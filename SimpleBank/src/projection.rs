@@ -0,0 +1,192 @@
+// Read models maintained from the events of a journal.
+//
+// A projection only sees events, one at a time and in order, so it can be
+// kept up to date live or rebuilt from a journal with `Journal::project`.
+// Projections only check what they keep, e.g. that a statement balance
+// doesn't go negative: use `Journal::rebuild` to check a journal coming from
+// an untrusted source first.
+
+use num_bigint::BigInt;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use crate::action::Action;
+use crate::amount::Amount;
+use crate::bank::ErrorMsg;
+use crate::event::Event;
+
+pub trait Projection<A = BigInt> {
+    /// Called for each event of the `seq`-th entry, emitted by `action`.
+    /// Fails if the event doesn't follow from the previous ones.
+    fn apply(&mut self, seq: usize, action: &Action, event: &Event<A>) -> Result<(), ErrorMsg>;
+}
+
+fn add<A: Amount>(total: &A, amount: &A) -> Result<A, ErrorMsg> {
+    total.checked_add(amount).ok_or_else(|| "Inconsistent journal: amount overflows".to_string())
+}
+
+fn sub<A: Amount>(total: &A, amount: &A) -> Result<A, ErrorMsg> {
+    total.checked_sub(amount).ok_or_else(|| "Inconsistent journal: amount underflows".to_string())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Movement<A = BigInt> {
+    Credit(A),
+    Debit(A),
+}
+
+impl<A: fmt::Display> fmt::Display for Movement<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Movement::Credit(amount) => f.pad(&format!("+{amount}")),
+            Movement::Debit(amount)  => f.pad(&format!("-{amount}")),
+        }
+    }
+}
+
+/// A line of an account statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatementLine<A = BigInt> {
+    pub seq:      usize,
+    pub action:   Action,
+    pub movement: Movement<A>,
+    /// Balance of the account right after the movement
+    pub balance:  A,
+}
+
+impl<A: fmt::Display> fmt::Display for StatementLine<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>6}  {:<40} {:>12} {:>12}",
+               format!("#{}", self.seq), self.action.to_string(), self.movement, self.balance.to_string())
+    }
+}
+
+/// Chronological ledger of every account.
+#[derive(Clone, Debug)]
+pub struct Statements<A = BigInt> {
+    lines: BTreeMap<String, Vec<StatementLine<A>>>,
+}
+
+impl<A: Amount> Default for Statements<A> {
+    fn default() -> Self {
+        Statements { lines: BTreeMap::new() }
+    }
+}
+
+impl<A: Amount> Statements<A> {
+    /// Lines of `account`, oldest first; empty for unknown accounts.
+    pub fn statement(&self, account: &str) -> &[StatementLine<A>] {
+        self.lines.get(account).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    fn push(&mut self, seq: usize, action: &Action, account: &str, movement: Movement<A>) -> Result<(), ErrorMsg> {
+        let lines   = self.lines.entry(account.to_string()).or_default();
+        let last    = lines.last().map(|line| line.balance.clone()).unwrap_or_else(A::zero);
+        let balance =
+            match &movement {
+                Movement::Credit(amount) => add(&last, amount)?,
+                Movement::Debit(amount)  => sub(&last, amount)?,
+            };
+
+        lines.push(StatementLine { seq, action: action.clone(), movement, balance });
+        Ok(())
+    }
+}
+
+impl<A: Amount> Projection<A> for Statements<A> {
+    fn apply(&mut self, seq: usize, action: &Action, event: &Event<A>) -> Result<(), ErrorMsg> {
+        match event {
+            Event::BalanceCredited { account, amount } =>
+                self.push(seq, action, account, Movement::Credit(amount.clone())),
+            Event::BalanceDebited { account, amount } =>
+                self.push(seq, action, account, Movement::Debit(amount.clone())),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Amount currently invested by each owner, owners with nothing invested are left out.
+#[derive(Clone, Debug)]
+pub struct InvestedPerOwner<A = BigInt> {
+    totals: BTreeMap<String, A>,
+}
+
+impl<A: Amount> Default for InvestedPerOwner<A> {
+    fn default() -> Self {
+        InvestedPerOwner { totals: BTreeMap::new() }
+    }
+}
+
+impl<A: Amount> InvestedPerOwner<A> {
+    pub fn invested(&self, owner: &str) -> A {
+        self.totals.get(owner).cloned().unwrap_or_else(A::zero)
+    }
+
+    pub fn totals(&self) -> &BTreeMap<String, A> {
+        &self.totals
+    }
+}
+
+impl<A: Amount> Projection<A> for InvestedPerOwner<A> {
+    fn apply(&mut self, _seq: usize, _action: &Action, event: &Event<A>) -> Result<(), ErrorMsg> {
+        match event {
+            Event::InvestmentCreated { owner, amount, .. } => {
+                let total = add(&self.invested(owner), amount)?;
+                self.totals.insert(owner.clone(), total);
+            }
+            Event::InvestmentClosed { owner, amount, .. } => {
+                let total = sub(&self.invested(owner), amount)?;
+                if total.is_zero() {
+                    self.totals.remove(owner);
+                }
+                else {
+                    self.totals.insert(owner.clone(), total);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Balances of every account, to rank them.
+#[derive(Clone, Debug)]
+pub struct Holders<A = BigInt> {
+    balances: BTreeMap<String, A>,
+}
+
+impl<A: Amount> Default for Holders<A> {
+    fn default() -> Self {
+        Holders { balances: BTreeMap::new() }
+    }
+}
+
+impl<A: Amount> Holders<A> {
+    /// The `n` largest balances, largest first, ties broken by account name.
+    pub fn top(&self, n: usize) -> Vec<(&str, &A)> {
+        let mut holders: Vec<_> = self.balances.iter().map(|(account, balance)| (account.as_str(), balance)).collect();
+        holders.sort_by_key(|&(account, balance)| (Reverse(balance), account));
+        holders.truncate(n);
+        holders
+    }
+}
+
+impl<A: Amount> Projection<A> for Holders<A> {
+    fn apply(&mut self, _seq: usize, _action: &Action, event: &Event<A>) -> Result<(), ErrorMsg> {
+        let (account, balance) =
+            match event {
+                Event::BalanceCredited { account, amount } => {
+                    let balance = self.balances.get(account).cloned().unwrap_or_else(A::zero);
+                    (account, add(&balance, amount)?)
+                }
+                Event::BalanceDebited { account, amount } => {
+                    let balance = self.balances.get(account).cloned().unwrap_or_else(A::zero);
+                    (account, sub(&balance, amount)?)
+                }
+                _ => return Ok(()),
+            };
+
+        self.balances.insert(account.clone(), balance);
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+// Transliteration of the pure definitions of bank.qnt.
+//
+// Functions take the state and return a new one, exactly like the spec,
+// and keep its edge cases: every account of the domain is in `balances`
+// from the start, so `get` is total over ACCOUNTS. Looking up a key outside
+// of the map panics, as it is a runtime error in Quint.
+
+use num_bigint::BigInt;
+use num_traits::Zero;
+use std::collections::BTreeMap;
+use crate::action::Action;
+use crate::bank::{FailedTransfer, Investment, RateLimit, Schedule, Spend};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BankState {
+    pub balances:    BTreeMap<String, BigInt>,
+    pub investments: BTreeMap<BigInt, Investment>,
+    pub next_id:     BigInt,
+    pub allowances:  BTreeMap<(String, String), BigInt>,
+    pub height:           BigInt,
+    pub schedules:        BTreeMap<BigInt, Schedule>,
+    pub next_schedule_id: BigInt,
+    pub failed_transfers: Vec<FailedTransfer>,
+    pub limits:           BTreeMap<String, RateLimit>,
+    pub spent:            BTreeMap<String, Vec<Spend>>,
+}
+
+pub type BankResult = Result<BankState, String>;
+
+// `map.setBy(key, f)`
+fn set_by(map: &BTreeMap<String, BigInt>, key: &str, f: impl Fn(&BigInt) -> BigInt) -> BTreeMap<String, BigInt> {
+    let mut map = map.clone();
+    let value = f(&map[key]);
+    map.insert(key.to_string(), value);
+    map
+}
+
+// `action init`
+pub fn init<S: AsRef<str>>(accounts: &[S]) -> BankState {
+    BankState {
+        balances:    accounts.iter().map(|acc| (acc.as_ref().to_string(), BigInt::zero())).collect(),
+        investments: BTreeMap::new(),
+        next_id:     BigInt::zero(),
+        allowances:  BTreeMap::new(),
+        height:           BigInt::zero(),
+        schedules:        BTreeMap::new(),
+        next_schedule_id: BigInt::zero(),
+        failed_transfers: Vec::new(),
+        limits:           BTreeMap::new(),
+        spent:            BTreeMap::new(),
+    }
+}
+
+fn window(bank_state: &BankState, account: &str, limit: &RateLimit) -> Vec<Spend> {
+    // `spent.getOrElse(account, [])`
+    let spent = bank_state.spent.get(account).cloned().unwrap_or_default();
+    spent.into_iter().filter(|s| s.height > &bank_state.height - &limit.blocks).collect()
+}
+
+fn spend(bank_state: &BankState, account: &str, amount: &BigInt) -> BankResult {
+    match bank_state.limits.get(account) {
+        None => Ok(bank_state.clone()),
+        Some(limit) => {
+            let mut recent = window(bank_state, account, limit);
+            let used: BigInt = recent.iter().map(|s| &s.amount).sum();
+            if used + amount > limit.max {
+                Err("Rate limited".to_string())
+            } else {
+                recent.push(Spend { height: bank_state.height.clone(), amount: amount.clone() });
+                let mut spent = bank_state.spent.clone();
+                spent.insert(account.to_string(), recent);
+                Ok(BankState { spent, ..bank_state.clone() })
+            }
+        }
+    }
+}
+
+pub fn set_limit(bank_state: &BankState, account: &str, max: &BigInt, blocks: &BigInt) -> BankResult {
+    if max < &BigInt::zero() {
+        Err("Limit can't be negative".to_string())
+    } else if blocks <= &BigInt::zero() {
+        Err("Window should be at least one block".to_string())
+    } else {
+        let mut limits = bank_state.limits.clone();
+        limits.insert(account.to_string(), RateLimit { max: max.clone(), blocks: blocks.clone() });
+        Ok(BankState {
+            limits,
+            ..bank_state.clone()
+        })
+    }
+}
+
+pub fn deposit(bank_state: &BankState, depositor: &str, amount: &BigInt) -> BankResult {
+    if amount <= &BigInt::zero() {
+        Err("Amount should be greater than zero".to_string())
+    } else {
+        Ok(BankState {
+            balances: set_by(&bank_state.balances, depositor, |current| current + amount),
+            ..bank_state.clone()
+        })
+    }
+}
+
+pub fn withdraw(bank_state: &BankState, withdrawer: &str, amount: &BigInt) -> BankResult {
+    if amount <= &BigInt::zero() {
+        Err("Amount should be greater than zero".to_string())
+    } else if &bank_state.balances[withdrawer] < amount {
+        Err("Balance is too low".to_string())
+    } else {
+        let spent = spend(bank_state, withdrawer, amount)?;
+        Ok(BankState {
+            balances: set_by(&spent.balances, withdrawer, |current| current - amount),
+            ..spent
+        })
+    }
+}
+
+pub fn transfer(bank_state: &BankState, sender: &str, receiver: &str, amount: &BigInt) -> BankResult {
+    if amount <= &BigInt::zero() {
+        Err("Amount should be greater than zero".to_string())
+    } else if &bank_state.balances[sender] < amount {
+        Err("Balance is too low".to_string())
+    } else {
+        let spent    = spend(bank_state, sender, amount)?;
+        let balances = set_by(&spent.balances, sender, |current| current - amount);
+        Ok(BankState {
+            balances: set_by(&balances, receiver, |current| current + amount),
+            ..spent
+        })
+    }
+}
+
+pub fn buy_investment(bank_state: &BankState, buyer: &str, amount: &BigInt) -> BankResult {
+    if amount <= &BigInt::zero() {
+        Err("Amount should be greater than zero".to_string())
+    } else if &bank_state.balances[buyer] < amount {
+        Err("Balance is too low".to_string())
+    } else {
+        let mut investments = bank_state.investments.clone();
+        investments.insert(
+            bank_state.next_id.clone(),
+            Investment { owner: buyer.to_string(), amount: amount.clone() },
+        );
+        Ok(BankState {
+            next_id:  &bank_state.next_id + 1,
+            investments,
+            balances: set_by(&bank_state.balances, buyer, |current| current - amount),
+            ..bank_state.clone()
+        })
+    }
+}
+
+pub fn sell_investment(bank_state: &BankState, seller: &str, id: &BigInt) -> BankResult {
+    match bank_state.investments.get(id) {
+        None => Err("No investment with this id".to_string()),
+        Some(investment) if investment.owner != seller =>
+            Err("Seller can't sell an investment they don't own".to_string()),
+        Some(investment) => {
+            let mut investments = bank_state.investments.clone();
+            investments.remove(id);
+            Ok(BankState {
+                investments,
+                balances: set_by(&bank_state.balances, seller, |current| current + &investment.amount),
+                ..bank_state.clone()
+            })
+        }
+    }
+}
+
+pub fn approve(bank_state: &BankState, owner: &str, spender: &str, amount: &BigInt) -> BankResult {
+    if amount < &BigInt::zero() {
+        Err("Allowance can't be negative".to_string())
+    } else {
+        let mut allowances = bank_state.allowances.clone();
+        allowances.insert((owner.to_string(), spender.to_string()), amount.clone());
+        Ok(BankState {
+            allowances,
+            ..bank_state.clone()
+        })
+    }
+}
+
+pub fn transfer_from(bank_state: &BankState, spender: &str, owner: &str, receiver: &str, amount: &BigInt) -> BankResult {
+    // `allowances.getOrElse((owner, spender), 0)`
+    let key     = (owner.to_string(), spender.to_string());
+    let allowed = bank_state.allowances.get(&key).cloned().unwrap_or_else(BigInt::zero);
+
+    if amount <= &BigInt::zero() {
+        Err("Amount should be greater than zero".to_string())
+    } else if &allowed < amount {
+        Err("Allowance is too low".to_string())
+    } else if &bank_state.balances[owner] < amount {
+        Err("Balance is too low".to_string())
+    } else {
+        let spent          = spend(bank_state, owner, amount)?;
+        let balances       = set_by(&spent.balances, owner, |current| current - amount);
+        let mut allowances = spent.allowances.clone();
+        allowances.insert(key, allowed - amount);
+        Ok(BankState {
+            balances: set_by(&balances, receiver, |current| current + amount),
+            allowances,
+            ..spent
+        })
+    }
+}
+
+pub fn schedule_transfer(bank_state: &BankState, sender: &str, receiver: &str, amount: &BigInt,
+                         at: &BigInt, every: &Option<BigInt>) -> BankResult {
+    let bad_period = match every {
+        Some(period) => period <= &BigInt::zero(),
+        None         => false,
+    };
+    if amount <= &BigInt::zero() {
+        Err("Amount should be greater than zero".to_string())
+    } else if at <= &bank_state.height {
+        Err("Transfer should be scheduled in the future".to_string())
+    } else if bad_period {
+        Err("Period should be greater than zero".to_string())
+    } else {
+        let schedule = Schedule {
+            sender:   sender.to_string(),
+            receiver: receiver.to_string(),
+            amount:   amount.clone(),
+            at:       at.clone(),
+            every:    every.clone(),
+        };
+        let mut schedules = bank_state.schedules.clone();
+        schedules.insert(bank_state.next_schedule_id.clone(), schedule);
+        Ok(BankState {
+            schedules,
+            next_schedule_id: &bank_state.next_schedule_id + 1,
+            ..bank_state.clone()
+        })
+    }
+}
+
+fn run_schedule(bank_state: &BankState, id: &BigInt) -> BankState {
+    let schedule = &bank_state.schedules[id];
+    let mut ran =
+        match transfer(bank_state, &schedule.sender, &schedule.receiver, &schedule.amount) {
+            Ok(next)    => next,
+            Err(reason) => {
+                let mut failed_transfers = bank_state.failed_transfers.clone();
+                failed_transfers.push(FailedTransfer { id: id.clone(), height: bank_state.height.clone(), reason });
+                BankState { failed_transfers, ..bank_state.clone() }
+            }
+        };
+    match &schedule.every {
+        Some(period) => {
+            ran.schedules.insert(id.clone(), Schedule { at: &schedule.at + period, ..schedule.clone() });
+        }
+        None => {
+            ran.schedules.remove(id);
+        }
+    }
+    ran
+}
+
+pub fn tick(bank_state: &BankState) -> BankResult {
+    let height  = &bank_state.height + BigInt::from(1);
+    let started = BankState { height: height.clone(), ..bank_state.clone() };
+
+    // `range(0, next_schedule_id).foldl(...)`
+    let mut state = started;
+    let mut id    = BigInt::zero();
+    while id < bank_state.next_schedule_id {
+        if state.schedules.get(&id).is_some_and(|schedule| schedule.at <= height) {
+            state = run_schedule(&state, &id);
+        }
+        id += 1;
+    }
+    Ok(state)
+}
+
+pub fn apply(bank_state: &BankState, action: &Action) -> BankResult {
+    match action {
+        Action::Deposit { depositor, amount }            => deposit(bank_state, depositor, amount),
+        Action::Withdraw { withdrawer, amount }          => withdraw(bank_state, withdrawer, amount),
+        Action::Transfer { sender, receiver, amount }    => transfer(bank_state, sender, receiver, amount),
+        Action::BuyInvestment { buyer, amount }          => buy_investment(bank_state, buyer, amount),
+        Action::SellInvestment { seller, investment_id } => sell_investment(bank_state, seller, investment_id),
+        Action::Approve { owner, spender, amount }       => approve(bank_state, owner, spender, amount),
+        Action::TransferFrom { spender, owner, receiver, amount } =>
+            transfer_from(bank_state, spender, owner, receiver, amount),
+        Action::ScheduleTransfer { sender, receiver, amount, at, every } =>
+            schedule_transfer(bank_state, sender, receiver, amount, at, every),
+        Action::Tick => tick(bank_state),
+        Action::SetLimit { account, max, blocks } => set_limit(bank_state, account, max, blocks),
+        // freezes are decided in front of the bank, see `auth`, and not modelled
+        Action::Freeze { .. } | Action::Unfreeze { .. } =>
+            Err(format!("{action} is not part of bank.qnt")),
+    }
+}
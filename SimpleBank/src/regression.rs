@@ -0,0 +1,257 @@
+// Turns an MBT trace into a standalone Rust regression test.
+//
+// The generated test only calls the public `BankState` API with the values
+// picked in the trace, so it keeps working once the trace itself is gone
+// or the spec has moved on.
+
+use num_bigint::BigInt;
+use std::fmt::Write;
+use crate::action::Action;
+use crate::bank::BankState;
+use crate::mbt::State;
+use crate::trace_reader::TraceError;
+
+// literal usable wherever a `T: From<i64>` is expected through `.into()`
+fn into_literal(n: &BigInt) -> String {
+    match i64::try_from(n) {
+        Ok(n) if n < 0 => format!("({n}).into()"),
+        Ok(n)          => format!("{n}.into()"),
+        Err(_)         => format!("{:?}.parse::<BigInt>().unwrap()", n.to_string()),
+    }
+}
+
+fn bigint_literal(n: &BigInt) -> String {
+    match i64::try_from(n) {
+        Ok(n)  => format!("BigInt::from({n})"),
+        Err(_) => format!("{:?}.parse::<BigInt>().unwrap()", n.to_string()),
+    }
+}
+
+fn string_literal(s: &str) -> String {
+    format!("{s:?}.into()")
+}
+
+fn option_literal(n: &Option<BigInt>) -> String {
+    match n {
+        Some(n) => format!("Some({})", into_literal(n)),
+        None    => "None".to_string(),
+    }
+}
+
+fn render_call(action: &Action) -> String {
+    match action {
+        Action::Deposit { depositor, amount } =>
+            format!("deposit({}, {})", string_literal(depositor), into_literal(amount)),
+        Action::Withdraw { withdrawer, amount } =>
+            format!("withdraw({}, {})", string_literal(withdrawer), into_literal(amount)),
+        Action::Transfer { sender, receiver, amount } =>
+            format!("transfer({}, {}, {})",
+                    string_literal(sender), string_literal(receiver), into_literal(amount)),
+        Action::BuyInvestment { buyer, amount } =>
+            format!("buy_investment({}, {})", string_literal(buyer), into_literal(amount)),
+        Action::SellInvestment { seller, investment_id } =>
+            format!("sell_investment({}, {})", string_literal(seller), into_literal(investment_id)),
+        Action::Approve { owner, spender, amount } =>
+            format!("approve({}, {}, {})",
+                    string_literal(owner), string_literal(spender), into_literal(amount)),
+        Action::TransferFrom { spender, owner, receiver, amount } =>
+            format!("transfer_from({}, {}, {}, {})",
+                    string_literal(spender), string_literal(owner), string_literal(receiver),
+                    into_literal(amount)),
+        Action::ScheduleTransfer { sender, receiver, amount, at, every } =>
+            format!("schedule_transfer({}, {}, {}, {}, {})",
+                    string_literal(sender), string_literal(receiver), into_literal(amount),
+                    into_literal(at), option_literal(every)),
+        Action::Tick =>
+            "tick()".to_string(),
+        Action::SetLimit { account, max, blocks } =>
+            format!("set_limit({}, {}, {})", string_literal(account), into_literal(max), into_literal(blocks)),
+        Action::Freeze { account } =>
+            format!("freeze({})", string_literal(account)),
+        Action::Unfreeze { account } =>
+            format!("unfreeze({})", string_literal(account)),
+    }
+}
+
+fn render_setup(out: &mut String, init: &BankState) {
+    writeln!(out, "    let mut bank_state = BankState::new();").unwrap();
+
+    for (account, balance) in &init.balances {
+        writeln!(out, "    bank_state.balances.insert({}, {});",
+                 string_literal(account), into_literal(balance)).unwrap();
+    }
+
+    for (id, investment) in &init.investments {
+        writeln!(out, "    bank_state.investments.insert({}, Investment {{ owner: {}, amount: {} }});",
+                 into_literal(id), string_literal(&investment.owner),
+                 into_literal(&investment.amount)).unwrap();
+    }
+
+    if init.next_id != BigInt::from(0) {
+        writeln!(out, "    bank_state.next_id = {};", into_literal(&init.next_id)).unwrap();
+    }
+
+    for ((owner, spender), amount) in &init.allowances {
+        writeln!(out, "    bank_state.allowances.insert(({}, {}), {});",
+                 string_literal(owner), string_literal(spender), into_literal(amount)).unwrap();
+    }
+
+    if init.height != BigInt::from(0) {
+        writeln!(out, "    bank_state.height = {};", into_literal(&init.height)).unwrap();
+    }
+
+    for (id, schedule) in &init.schedules {
+        writeln!(out, "    bank_state.schedules.insert({}, Schedule {{ sender: {}, receiver: {}, amount: {}, at: {}, every: {} }});",
+                 into_literal(id), string_literal(&schedule.sender), string_literal(&schedule.receiver),
+                 into_literal(&schedule.amount), into_literal(&schedule.at), option_literal(&schedule.every)).unwrap();
+    }
+
+    if init.next_schedule_id != BigInt::from(0) {
+        writeln!(out, "    bank_state.next_schedule_id = {};", into_literal(&init.next_schedule_id)).unwrap();
+    }
+
+    for (account, limit) in &init.limits {
+        writeln!(out, "    bank_state.limits.insert({}, RateLimit {{ max: {}, blocks: {} }});",
+                 string_literal(account), into_literal(&limit.max), into_literal(&limit.blocks)).unwrap();
+    }
+
+    for (account, spent) in &init.spent {
+        let spent: Vec<String> = spent.iter()
+            .map(|spend| format!("Spend {{ height: {}, amount: {} }}", into_literal(&spend.height), into_literal(&spend.amount)))
+            .collect();
+        writeln!(out, "    bank_state.spent.insert({}, [{}].into_iter().collect());",
+                 string_literal(account), spent.join(", ")).unwrap();
+    }
+}
+
+fn render_checks(out: &mut String, last: &BankState) {
+    writeln!(out, "    assert_eq!(bank_state.balances.len(), {});", last.balances.len()).unwrap();
+    for (account, balance) in &last.balances {
+        writeln!(out, "    assert_eq!(bank_state.balances[{account:?}], {});",
+                 bigint_literal(balance)).unwrap();
+    }
+
+    if last.investments.is_empty() {
+        writeln!(out, "    assert!(bank_state.investments.is_empty());").unwrap();
+    }
+    else {
+        writeln!(out, "    assert_eq!(bank_state.investments.len(), {});",
+                 last.investments.len()).unwrap();
+    }
+    for (id, investment) in &last.investments {
+        writeln!(out, "    assert_eq!(bank_state.investments[&{}].owner, {:?});",
+                 bigint_literal(id), investment.owner).unwrap();
+        writeln!(out, "    assert_eq!(bank_state.investments[&{}].amount, {});",
+                 bigint_literal(id), bigint_literal(&investment.amount)).unwrap();
+    }
+
+    writeln!(out, "    assert_eq!(bank_state.next_id, {});", bigint_literal(&last.next_id)).unwrap();
+
+    for ((owner, spender), amount) in &last.allowances {
+        writeln!(out, "    assert_eq!(bank_state.allowance({owner:?}, {spender:?}), {});",
+                 bigint_literal(amount)).unwrap();
+    }
+
+    if last.height != BigInt::from(0) {
+        writeln!(out, "    assert_eq!(bank_state.height, {});", bigint_literal(&last.height)).unwrap();
+    }
+    for (id, schedule) in &last.schedules {
+        writeln!(out, "    assert_eq!(bank_state.schedules[&{}].at, {});",
+                 bigint_literal(id), bigint_literal(&schedule.at)).unwrap();
+    }
+    if !last.schedules.is_empty() || !last.failed_transfers.is_empty() {
+        writeln!(out, "    assert_eq!(bank_state.schedules.len(), {});", last.schedules.len()).unwrap();
+        writeln!(out, "    assert_eq!(bank_state.failed_transfers.len(), {});", last.failed_transfers.len()).unwrap();
+    }
+
+    for (account, limit) in &last.limits {
+        writeln!(out, "    assert_eq!(bank_state.limits[{account:?}].max, {});", bigint_literal(&limit.max)).unwrap();
+    }
+    for (account, spent) in &last.spent {
+        writeln!(out, "    assert_eq!(bank_state.spent[{account:?}].len(), {});", spent.len()).unwrap();
+    }
+}
+
+/// Valid Rust identifier derived from a trace file name.
+pub fn test_name(file_name: &str) -> String {
+    let stem = file_name.split('.').next().unwrap_or(file_name);
+    let name: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    }
+    else {
+        format!("trace_{name}")
+    }
+}
+
+/// Renders a `#[test]` named `name` replaying `states` step by step.
+pub fn render_test<I>(name: &str, source: &str, states: I) -> Result<String, String>
+where
+    I: IntoIterator<Item = Result<itf::State<State>, TraceError>>,
+{
+    let mut states = states.into_iter();
+
+    let init = states
+        .next()
+        .ok_or("Empty trace".to_string())?
+        .map_err(|e| e.to_string())?
+        .value;
+
+    if init.action()?.is_some() {
+        return Err("Trace does not start with init".to_string());
+    }
+
+    let mut body = String::new();
+    render_setup(&mut body, &init.bank_state);
+
+    let mut last = init.bank_state;
+    for state in states {
+        let state  = state.map_err(|e| e.to_string())?;
+        let action = state.value.action()?
+                          .ok_or("Unexpected init in the middle of the trace".to_string())?;
+        let expect = if state.value.error.is_some() { "is_err" } else { "is_ok" };
+
+        writeln!(body).unwrap();
+        if let Some(index) = state.meta.index {
+            writeln!(body, "    // step {index}").unwrap();
+        }
+        writeln!(body, "    assert!(bank_state.{}.{expect}());", render_call(&action)).unwrap();
+
+        last = state.value.bank_state;
+    }
+
+    writeln!(body).unwrap();
+    render_checks(&mut body, &last);
+
+    let mut types = vec!["BankState"];
+    if body.contains("Investment {") {
+        types.push("Investment");
+    }
+    if body.contains("Schedule {") {
+        types.push("Schedule");
+    }
+    if body.contains("RateLimit {") {
+        types.push("RateLimit");
+    }
+    if body.contains("Spend {") {
+        types.push("Spend");
+    }
+    let imports = if types.len() == 1 { types[0].to_string() } else { format!("{{{}}}", types.join(", ")) };
+
+    let mut out = String::new();
+    writeln!(out, "// Generated by `trace_to_test` from {source}.").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "use num_bigint::BigInt;").unwrap();
+    writeln!(out, "use simple_bank::bank::{imports};").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "#[test]").unwrap();
+    writeln!(out, "fn {name}() {{").unwrap();
+    out.push_str(&body);
+    writeln!(out, "}}").unwrap();
+
+    Ok(out)
+}
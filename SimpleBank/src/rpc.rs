@@ -0,0 +1,231 @@
+// JSON-RPC 2.0 front-end to the bank, over TCP.
+//
+// Each line sent by a client is a request, answered by exactly one line, or a
+// notification without an `id`, which is carried out but never answered, not
+// even on failure. Lines that don't parse are answered with a null `id`.
+// Actions use the same serde form as the action log, e.g.
+//
+//     {"jsonrpc": "2.0", "id": 1, "method": "apply",
+//      "params": {"Deposit": {"depositor": "Alice", "amount": "5"}}}
+//
+// Callers log in once per connection with the token the server was started
+// with for their account, then act as that account, see `auth`. Accounts
+// without a token can't log in, but anyone can read the state.
+//
+// Methods:
+//   login       {"account": str, "token": str} -> null
+//   logout      -                   -> null
+//   apply       Action              -> list of events, or the bank error
+//   freeze      {"account": str}    -> null, admins only, same as applying Freeze
+//   unfreeze    {"account": str}    -> null, admins only, same as applying Unfreeze
+//   state       -                   -> BankState
+//   balance     {"account": str}    -> amount, null for unknown accounts
+//   investment  {"id": str}         -> Investment, null for unknown ids
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use serde_with::DisplayFromStr;
+use serde_with_macros::serde_as;
+use simple_bank::action::Action;
+use simple_bank::auth::Unauthorized;
+
+use crate::{ActionError, BankApp};
+
+const PARSE_ERROR: i64      = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64   = -32602;
+const BANK_ERROR: i64       = -32000;
+const UNAUTHORIZED: i64     = -32001;
+
+#[derive(Deserialize)]
+struct Request {
+    // `None` for notifications, while `"id": null` is a request
+    #[serde(default, deserialize_with = "present")]
+    id:     Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+fn present<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+#[derive(Serialize)]
+struct RpcError {
+    code:    i64,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id:      Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result:  Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error:   Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct AccountParams {
+    account: String,
+}
+
+#[derive(Deserialize)]
+struct LoginParams {
+    account: String,
+    token:   String,
+}
+
+#[serde_as]
+#[derive(Deserialize)]
+struct InvestmentParams {
+    #[serde_as(as = "DisplayFromStr")]
+    id: BigInt,
+}
+
+fn params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError { code: INVALID_PARAMS, message: e.to_string() })
+}
+
+fn unauthorized(e: impl ToString) -> RpcError {
+    RpcError { code: UNAUTHORIZED, message: e.to_string() }
+}
+
+fn action_error(e: ActionError) -> RpcError {
+    match e {
+        ActionError::Unauthorized(e) => unauthorized(e),
+        ActionError::Bank(message)   => RpcError { code: BANK_ERROR, message },
+    }
+}
+
+// Compares every byte whatever the first difference, so that the time taken
+// doesn't tell how much of a guess is right.
+fn same_token(given: &str, expected: &str) -> bool {
+    let (given, expected) = (given.as_bytes(), expected.as_bytes());
+    given.len() == expected.len()
+        && given.iter().zip(expected).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+// `caller` is the account the connection is logged in as
+fn call(
+    app:     &Mutex<BankApp>,
+    tokens:  &BTreeMap<String, String>,
+    caller:  &mut Option<String>,
+    request: Request,
+) -> Result<Value, RpcError> {
+    let mut app = app.lock().unwrap();
+
+    match request.method.as_str() {
+        "login" => {
+            let LoginParams { account, token } = params(request.params)?;
+            if !tokens.get(&account).is_some_and(|expected| same_token(&token, expected)) {
+                return Err(unauthorized(Unauthorized::BadCredentials { account }));
+            }
+            *caller = Some(account);
+            Ok(Value::Null)
+        }
+        "logout" => {
+            *caller = None;
+            Ok(Value::Null)
+        }
+        "apply" => {
+            let action: Action = params(request.params)?;
+            app.apply_action(caller.as_deref(), action)
+               .map(|events| json!(events))
+               .map_err(action_error)
+        }
+        "freeze" | "unfreeze" => {
+            let AccountParams { account } = params(request.params)?;
+            let action =
+                if request.method == "freeze" {
+                    Action::Freeze { account }
+                }
+                else {
+                    Action::Unfreeze { account }
+                };
+            app.apply_action(caller.as_deref(), action)
+               .map(|_events| Value::Null)
+               .map_err(action_error)
+        }
+        "state" => Ok(json!(app.state)),
+        "balance" => {
+            let AccountParams { account } = params(request.params)?;
+            Ok(json!(app.state.balances.get(&account).map(ToString::to_string)))
+        }
+        "investment" => {
+            let InvestmentParams { id } = params(request.params)?;
+            Ok(json!(app.state.investments.get(&id)))
+        }
+        method => Err(RpcError {
+            code:    METHOD_NOT_FOUND,
+            message: format!("Unknown method: {method}"),
+        }),
+    }
+}
+
+// `None` for notifications, which get no response
+fn respond(app: &Mutex<BankApp>, tokens: &BTreeMap<String, String>, caller: &mut Option<String>, line: &str) -> Option<Response> {
+    let (id, outcome) =
+        match serde_json::from_str::<Request>(line) {
+            Ok(request) => (request.id.clone(), call(app, tokens, caller, request)),
+            Err(e)      => (Some(Value::Null), Err(RpcError { code: PARSE_ERROR, message: e.to_string() })),
+        };
+    let id = id?;
+
+    Some(match outcome {
+        Ok(result) => Response { jsonrpc: "2.0", id, result: Some(result), error: None },
+        Err(error) => Response { jsonrpc: "2.0", id, result: None, error: Some(error) },
+    })
+}
+
+fn handle(app: &Mutex<BankApp>, tokens: &BTreeMap<String, String>, stream: TcpStream) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut caller = None;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let Some(response) = respond(app, tokens, &mut caller, &line) else {
+            continue;
+        };
+        writeln!(writer, "{}", serde_json::to_string(&response).unwrap())?;
+    }
+
+    Ok(())
+}
+
+/// Serves the bank on `addr` until the process is killed, one thread per client.
+/// Accounts log in with their token in `tokens`, or not at all without one.
+pub fn serve(app: BankApp, addr: &str, tokens: BTreeMap<String, String>) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let app      = Arc::new(Mutex::new(app));
+    let tokens   = Arc::new(tokens);
+
+    println!("Listening on {}", listener.local_addr()?);
+    app.lock().unwrap().log_state();
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let app    = app.clone();
+        let tokens = tokens.clone();
+
+        thread::spawn(move || {
+            if let Err(err) = handle(&app, &tokens, stream) {
+                eprintln!("Client error: {err}");
+            }
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+// A `BankState` shared by concurrent clients.
+//
+// The state is owned by a single worker thread, and clients send it actions
+// over a channel. Actions are applied one at a time in the order the worker
+// receives them, which is the linearization of concurrent calls: each call
+// takes effect at some point between its invocation and its return, and the
+// recorded history lists every call in that order.
+
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+use crate::action::Action;
+use crate::bank::{BankState, ErrorMsg, Events};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub action: Action,
+    pub result: Result<Events, ErrorMsg>,
+}
+
+/// Linear history of the calls to a `BankService`.
+#[derive(Clone, Debug)]
+pub struct History {
+    pub initial: BankState,
+    pub entries: Vec<Entry>,
+}
+
+impl History {
+    /// Replays the history sequentially on a plain `BankState`, checking
+    /// that every action has the same outcome as in the service.
+    pub fn replay(&self) -> Result<BankState, String> {
+        let mut state = self.initial.clone();
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let result = state.apply(entry.action.clone());
+            if result != entry.result {
+                return Err(format!(
+                    "Entry {i} ({}) returned {:?} in the service but {result:?} on replay",
+                    entry.action, entry.result
+                ));
+            }
+        }
+
+        Ok(state)
+    }
+}
+
+enum Command {
+    Apply(Action, Sender<Result<Events, ErrorMsg>>),
+    State(Sender<BankState>),
+    History(Sender<History>),
+}
+
+/// Handle to a bank service, clone it to share it between threads.
+#[derive(Clone)]
+pub struct BankService {
+    commands: Sender<Command>,
+}
+
+impl BankService {
+    /// Starts the worker thread, which stops once every handle is dropped.
+    pub fn spawn(initial: BankState) -> Self {
+        let (commands, requests) = channel();
+
+        thread::spawn(move || {
+            let mut state   = initial.clone();
+            let mut history = History { initial, entries: Vec::new() };
+
+            for command in requests {
+                // a client hanging up before its answer is not our concern
+                match command {
+                    Command::Apply(action, reply) => {
+                        let result = state.apply(action.clone());
+                        history.entries.push(Entry { action, result: result.clone() });
+                        let _ = reply.send(result);
+                    }
+                    Command::State(reply) => {
+                        let _ = reply.send(state.clone());
+                    }
+                    Command::History(reply) => {
+                        let _ = reply.send(history.clone());
+                    }
+                }
+            }
+        });
+
+        BankService { commands }
+    }
+
+    fn request<T>(&self, command: impl FnOnce(Sender<T>) -> Command) -> T {
+        let (reply, answer) = channel();
+        self.commands.send(command(reply)).expect("Bank service stopped");
+        answer.recv().expect("Bank service stopped")
+    }
+
+    pub fn apply(&self, action: Action) -> Result<Events, ErrorMsg> {
+        self.request(|reply| Command::Apply(action, reply))
+    }
+
+    /// Snapshot of the current state, cheap thanks to persistent maps.
+    pub fn state(&self) -> BankState {
+        self.request(Command::State)
+    }
+
+    pub fn history(&self) -> History {
+        self.request(Command::History)
+    }
+}
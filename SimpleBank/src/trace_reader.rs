@@ -0,0 +1,289 @@
+// Incremental reader for ITF traces.
+//
+// `itf::trace_from_str` needs the whole trace in memory, which does not scale
+// to long simulations. `TraceReader` only scans the top-level JSON object,
+// skips everything but the `states` array, and decodes one state at a time.
+
+use itf::trace::Meta;
+use itf::{State, Value};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::marker::PhantomData;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum TraceError {
+    Io(io::Error),
+    Syntax(String),
+    Itf(itf::Error),
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceError::Io(e)     => write!(f, "I/O error: {e}"),
+            TraceError::Syntax(e) => write!(f, "malformed trace: {e}"),
+            TraceError::Itf(e)    => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+impl From<io::Error> for TraceError {
+    fn from(e: io::Error) -> Self {
+        TraceError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for TraceError {
+    fn from(e: serde_json::Error) -> Self {
+        TraceError::Itf(itf::Error::Json(e))
+    }
+}
+
+impl From<itf::Error> for TraceError {
+    fn from(e: itf::Error) -> Self {
+        TraceError::Itf(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Position {
+    Start,
+    Fields,
+    States,
+    Done,
+}
+
+/// Yields the states of an ITF trace one by one.
+///
+/// Only the state currently being decoded is held in memory.
+/// `#meta` and `vars` are available once they have been read,
+/// i.e. as soon as the first state is out if they precede `states`
+/// in the file (as they do in traces produced by Quint).
+pub struct TraceReader<R, S> {
+    input:    R,
+    position: Position,
+    buffer:   Vec<u8>,
+    meta:     Option<Meta>,
+    vars:     Vec<String>,
+    _state:   PhantomData<S>,
+}
+
+impl<S> TraceReader<Box<dyn BufRead>, S> {
+    /// Opens a trace file, or reads from stdin if `path` is `-`.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let input: Box<dyn BufRead> =
+            if path == Path::new("-") {
+                Box::new(io::stdin().lock())
+            }
+            else {
+                Box::new(BufReader::new(File::open(path)?))
+            };
+
+        Ok(TraceReader::new(input))
+    }
+}
+
+impl<R: BufRead, S> TraceReader<R, S> {
+    pub fn new(input: R) -> Self {
+        TraceReader {
+            input,
+            position: Position::Start,
+            buffer:   Vec::new(),
+            meta:     None,
+            vars:     Vec::new(),
+            _state:   PhantomData,
+        }
+    }
+
+    pub fn meta(&self) -> Option<&Meta> {
+        self.meta.as_ref()
+    }
+
+    pub fn vars(&self) -> &[String] {
+        &self.vars
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, TraceError> {
+        Ok(self.input.fill_buf()?.first().copied())
+    }
+
+    fn bump(&mut self) {
+        self.input.consume(1);
+    }
+
+    fn peek_non_ws(&mut self) -> Result<Option<u8>, TraceError> {
+        while let Some(b) = self.peek()? {
+            if !b.is_ascii_whitespace() {
+                return Ok(Some(b));
+            }
+            self.bump();
+        }
+        Ok(None)
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), TraceError> {
+        match self.peek_non_ws()? {
+            Some(b) if b == expected => {
+                self.bump();
+                Ok(())
+            }
+            Some(b) => Err(TraceError::Syntax(
+                format!("expected '{}', found '{}'", expected as char, b as char))),
+            None => Err(TraceError::Syntax(
+                format!("expected '{}', found end of input", expected as char))),
+        }
+    }
+
+    // copies a JSON string literal, quotes included, into the buffer
+    fn read_string(&mut self) -> Result<(), TraceError> {
+        self.buffer.push(b'"');
+        self.bump();
+
+        let mut escaped = false;
+        loop {
+            let b = self.peek()?
+                        .ok_or(TraceError::Syntax("unterminated string".to_string()))?;
+            self.buffer.push(b);
+            self.bump();
+
+            match b {
+                b'"' if !escaped => return Ok(()),
+                b'\\'            => escaped = !escaped,
+                _                => escaped = false,
+            }
+        }
+    }
+
+    // copies the next JSON value, whatever its shape, into the buffer
+    fn read_value(&mut self) -> Result<(), TraceError> {
+        self.buffer.clear();
+        self.peek_non_ws()?;
+
+        let mut depth = 0usize;
+        loop {
+            let Some(b) = self.peek()? else {
+                return if depth == 0 && !self.buffer.is_empty() {
+                    Ok(())
+                }
+                else {
+                    Err(TraceError::Syntax("unexpected end of input".to_string()))
+                };
+            };
+
+            match b {
+                b'"' => self.read_string()?,
+                b'{' | b'[' => {
+                    depth += 1;
+                    self.buffer.push(b);
+                    self.bump();
+                }
+                b'}' | b']' if depth > 0 => {
+                    depth -= 1;
+                    self.buffer.push(b);
+                    self.bump();
+                }
+                b'}' | b']' => return Ok(()),
+                b if depth == 0 && (b == b',' || b.is_ascii_whitespace()) => return Ok(()),
+                b => {
+                    self.buffer.push(b);
+                    self.bump();
+                }
+            }
+
+            if depth == 0 && matches!(b, b'"' | b'}' | b']') {
+                return Ok(());
+            }
+        }
+    }
+
+    // reads top-level fields until the `states` array is entered or the object ends
+    fn seek_states(&mut self) -> Result<(), TraceError> {
+        if self.position == Position::Start {
+            self.expect(b'{')?;
+            self.position = Position::Fields;
+        }
+
+        loop {
+            match self.peek_non_ws()? {
+                Some(b'}') => {
+                    self.bump();
+                    self.position = Position::Done;
+                    return Ok(());
+                }
+                Some(b',') => self.bump(),
+                Some(b'"') => {
+                    self.read_value()?;
+                    let key: String = serde_json::from_slice(&self.buffer)?;
+                    self.expect(b':')?;
+
+                    match key.as_str() {
+                        "states" => {
+                            self.expect(b'[')?;
+                            self.position = Position::States;
+                            return Ok(());
+                        }
+                        "#meta" => {
+                            self.read_value()?;
+                            self.meta = Some(serde_json::from_slice(&self.buffer)?);
+                        }
+                        "vars" => {
+                            self.read_value()?;
+                            self.vars = serde_json::from_slice(&self.buffer)?;
+                        }
+                        _ => self.read_value()?,
+                    }
+                }
+                Some(b) => return Err(TraceError::Syntax(
+                    format!("unexpected '{}' in trace object", b as char))),
+                None => return Err(TraceError::Syntax(
+                    "unexpected end of input".to_string())),
+            }
+        }
+    }
+
+    fn next_state(&mut self) -> Result<Option<State<Value>>, TraceError> {
+        loop {
+            match self.position {
+                Position::Start | Position::Fields => self.seek_states()?,
+                Position::Done => return Ok(None),
+                Position::States => {
+                    match self.peek_non_ws()? {
+                        Some(b']') => {
+                            self.bump();
+                            self.position = Position::Fields;
+                        }
+                        Some(b',') => self.bump(),
+                        Some(_) => {
+                            self.read_value()?;
+                            return Ok(Some(serde_json::from_slice(&self.buffer)?));
+                        }
+                        None => return Err(TraceError::Syntax(
+                            "unterminated states array".to_string())),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<R: BufRead, S: DeserializeOwned> Iterator for TraceReader<R, S> {
+    type Item = Result<State<S>, TraceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.next_state().and_then(|state| {
+            state.map(|s| s.decode().map_err(TraceError::from)).transpose()
+        });
+
+        // stop at the first error, the rest of the input is not trustworthy
+        if result.is_err() {
+            self.position = Position::Done;
+        }
+
+        result.transpose()
+    }
+}
@@ -0,0 +1,71 @@
+use num_bigint::BigInt;
+use simple_bank::bank::BankState;
+use simple_bank::event::Event;
+
+fn funded() -> BankState {
+    let mut bank_state = BankState::new();
+    bank_state.deposit("Alice".into(), 10.into()).unwrap();
+    bank_state
+}
+
+#[test]
+fn transfer_from_spends_the_allowance() {
+    let mut bank_state = funded();
+
+    assert!(bank_state.approve("Alice".into(), "Bob".into(), 6.into()).is_ok());
+    assert_eq!(
+        bank_state.transfer_from("Bob".into(), "Alice".into(), "Charlie".into(), 4.into()),
+        Ok(vec![
+            Event::AllowanceChanged { owner: "Alice".into(), spender: "Bob".into(), amount: 2.into() },
+            Event::BalanceDebited { account: "Alice".into(), amount: 4.into() },
+            Event::BalanceCredited { account: "Charlie".into(), amount: 4.into() },
+        ])
+    );
+
+    assert_eq!(bank_state.allowance("Alice", "Bob"), BigInt::from(2));
+    assert_eq!(bank_state.balances["Alice"], BigInt::from(6));
+    assert_eq!(bank_state.balances["Charlie"], BigInt::from(4));
+
+    assert_eq!(
+        bank_state.transfer_from("Bob".into(), "Alice".into(), "Charlie".into(), 3.into()),
+        Err("Allowance is too low".to_string())
+    );
+}
+
+#[test]
+fn allowances_are_per_spender_and_replaced_on_approve() {
+    let mut bank_state = funded();
+
+    bank_state.approve("Alice".into(), "Bob".into(), 6.into()).unwrap();
+    bank_state.approve("Alice".into(), "Bob".into(), 1.into()).unwrap();
+
+    assert_eq!(bank_state.allowance("Alice", "Bob"), BigInt::from(1));
+    assert_eq!(bank_state.allowance("Alice", "Charlie"), BigInt::from(0));
+    assert!(bank_state.transfer_from("Charlie".into(), "Alice".into(), "Charlie".into(), 1.into()).is_err());
+
+    // revoking keeps the entry, like `put` in the spec
+    bank_state.approve("Alice".into(), "Bob".into(), 0.into()).unwrap();
+    assert_eq!(bank_state.allowances.len(), 1);
+    assert!(bank_state.approve("Alice".into(), "Bob".into(), (-1).into()).is_err());
+}
+
+#[test]
+fn failed_transfer_from_leaves_the_allowance() {
+    let mut bank_state = funded();
+    bank_state.approve("Alice".into(), "Bob".into(), 20.into()).unwrap();
+
+    assert_eq!(
+        bank_state.transfer_from("Bob".into(), "Alice".into(), "Bob".into(), 11.into()),
+        Err("Balance is too low".to_string())
+    );
+    assert_eq!(bank_state.allowance("Alice", "Bob"), BigInt::from(20));
+}
+
+#[test]
+fn allowances_are_logged_by_owner() {
+    let mut bank_state = funded();
+    bank_state.approve("Alice".into(), "Bob".into(), 3.into()).unwrap();
+
+    let logged = serde_json::to_value(&bank_state).unwrap();
+    assert_eq!(logged["allowances"], serde_json::json!({ "Alice": { "Bob": "3" } }));
+}
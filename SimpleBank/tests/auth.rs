@@ -0,0 +1,82 @@
+use simple_bank::action::Action;
+use simple_bank::auth::{Permissions, Unauthorized};
+use simple_bank::bank::BankState;
+
+fn action(line: &str) -> Action {
+    line.parse().unwrap()
+}
+
+#[test]
+fn owners_debit_their_own_accounts() {
+    let permissions = Permissions::default();
+    let state       = BankState::new();
+
+    assert_eq!(permissions.authorize(&state, "Bob", &action("deposit Alice 5")), Ok(()));
+    assert_eq!(permissions.authorize(&state, "Alice", &action("withdraw Alice 5")), Ok(()));
+    assert_eq!(permissions.authorize(&state, "Alice", &action("sell_investment Alice 0")), Ok(()));
+
+    for line in [
+        "withdraw Alice 5",
+        "transfer Alice Bob 5",
+        "buy_investment Alice 5",
+        "sell_investment Alice 0",
+        "approve Alice Bob 5",
+    ] {
+        assert_eq!(
+            permissions.authorize(&state, "Bob", &action(line)),
+            Err(Unauthorized::NotOwner { caller: "Bob".into(), account: "Alice".into() })
+        );
+    }
+}
+
+#[test]
+fn only_spenders_use_their_allowances() {
+    let permissions = Permissions::default();
+    let state       = BankState::new();
+
+    assert_eq!(permissions.authorize(&state, "Bob", &action("transfer_from Bob Alice Charlie 5")), Ok(()));
+    for caller in ["Alice", "Charlie"] {
+        assert_eq!(
+            permissions.authorize(&state, caller, &action("transfer_from Bob Alice Charlie 5")),
+            Err(Unauthorized::NotSpender { caller: caller.into(), spender: "Bob".into() })
+        );
+    }
+}
+
+#[test]
+fn only_admins_freeze_accounts() {
+    let permissions = Permissions::new(["Root"]);
+    let mut state   = BankState::new();
+
+    for line in ["freeze Bob", "unfreeze Bob"] {
+        assert_eq!(permissions.authorize(&state, "Root", &action(line)), Ok(()));
+        assert_eq!(
+            permissions.authorize(&state, "Alice", &action(line)),
+            Err(Unauthorized::NotAdmin { caller: "Alice".into() })
+        );
+    }
+
+    state.apply(action("freeze Bob")).unwrap();
+    let frozen = Err(Unauthorized::Frozen { account: "Bob".into() });
+    assert_eq!(permissions.authorize(&state, "Bob", &action("transfer Bob Alice 1")), frozen);
+    assert_eq!(permissions.authorize(&state, "Alice", &action("transfer_from Alice Bob Alice 1")), frozen);
+    // frozen accounts can still be credited
+    assert_eq!(permissions.authorize(&state, "Alice", &action("transfer Alice Bob 1")), Ok(()));
+
+    state.apply(action("unfreeze Bob")).unwrap();
+    assert_eq!(permissions.authorize(&state, "Bob", &action("transfer Bob Alice 1")), Ok(()));
+}
+
+#[test]
+fn only_admins_tick_and_set_limits() {
+    let permissions = Permissions::new(["Root"]);
+    let state       = BankState::new();
+
+    for line in ["tick", "set_limit Alice 10 5"] {
+        assert_eq!(permissions.authorize(&state, "Root", &action(line)), Ok(()));
+        assert_eq!(
+            permissions.authorize(&state, "Alice", &action(line)),
+            Err(Unauthorized::NotAdmin { caller: "Alice".into() })
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use simple_bank::bank::BankState;
+
+#[test]
+fn deposit_overflow_is_an_error() {
+    let mut bank_state = BankState::<u64>::default();
+
+    assert!(bank_state.deposit("Alice".into(), u64::MAX).is_ok());
+    assert!(bank_state.deposit("Alice".into(), 1).is_err());
+    assert_eq!(bank_state.balances["Alice"], u64::MAX);
+}
+
+#[test]
+fn transfer_overflow_leaves_both_balances_unchanged() {
+    let mut bank_state = BankState::<u128>::default();
+
+    assert!(bank_state.deposit("Alice".into(), 10).is_ok());
+    assert!(bank_state.deposit("Bob".into(), u128::MAX - 5).is_ok());
+
+    assert!(bank_state.transfer("Alice".into(), "Bob".into(), 6).is_err());
+    assert_eq!(bank_state.balances["Alice"], 10);
+    assert_eq!(bank_state.balances["Bob"], u128::MAX - 5);
+
+    assert!(bank_state.transfer("Alice".into(), "Bob".into(), 5).is_ok());
+    assert_eq!(bank_state.balances["Bob"], u128::MAX);
+}
+
+#[test]
+fn self_transfer_of_full_balance_does_not_overflow() {
+    let mut bank_state = BankState::<u64>::default();
+
+    assert!(bank_state.deposit("Alice".into(), u64::MAX).is_ok());
+    assert!(bank_state.transfer("Alice".into(), "Alice".into(), u64::MAX).is_ok());
+    assert_eq!(bank_state.balances["Alice"], u64::MAX);
+}
+
+#[test]
+fn selling_back_into_a_full_balance_is_an_error() {
+    let mut bank_state = BankState::<u64>::default();
+
+    assert!(bank_state.deposit("Alice".into(), u64::MAX).is_ok());
+    assert!(bank_state.buy_investment("Alice".into(), 1).is_ok());
+    assert!(bank_state.deposit("Alice".into(), 1).is_ok());
+
+    assert!(bank_state.sell_investment("Alice".into(), 0.into()).is_err());
+    assert_eq!(bank_state.investments.len(), 1);
+}
@@ -0,0 +1,26 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use simple_bank::differential::run;
+use simple_bank::explorer::Domains;
+
+#[test]
+fn implementation_agrees_with_reference_model() {
+    for seed in 0..50 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let result = run(&mut rng, &Domains::spec(), 100, false);
+        assert!(result.is_ok(), "seed {seed}: {:?}", result.unwrap_err());
+    }
+}
+
+// Unknown accounts are an error in the implementation, whereas the spec
+// starts every account at zero, so only the error messages differ.
+#[test]
+fn unknown_accounts_fail_with_a_different_error() {
+    let disagreement = (0..50)
+        .find_map(|seed| run(&mut StdRng::seed_from_u64(seed), &Domains::spec(), 100, true).err())
+        .expect("some sequence debits an account before funding it");
+
+    assert_eq!(disagreement.reason, "the errors differ");
+    assert!(disagreement.implementation.unwrap_err().starts_with("Could not find"));
+    assert_eq!(disagreement.reference.unwrap_err(), "Balance is too low");
+}
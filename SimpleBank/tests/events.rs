@@ -0,0 +1,122 @@
+use num_bigint::BigInt;
+use num_traits::Zero;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::BTreeMap;
+use simple_bank::bank::BankState;
+use simple_bank::differential::random_action;
+use simple_bank::event::Event;
+use simple_bank::explorer::Domains;
+
+#[test]
+fn operations_describe_their_changes() {
+    let mut bank_state = BankState::new();
+
+    assert_eq!(
+        bank_state.deposit("Alice".into(), 10.into()),
+        Ok(vec![Event::BalanceCredited { account: "Alice".into(), amount: 10.into() }])
+    );
+    assert_eq!(
+        bank_state.transfer("Alice".into(), "Bob".into(), 3.into()),
+        Ok(vec![
+            Event::BalanceDebited { account: "Alice".into(), amount: 3.into() },
+            Event::BalanceCredited { account: "Bob".into(), amount: 3.into() },
+        ])
+    );
+    assert_eq!(
+        bank_state.buy_investment("Bob".into(), 2.into()),
+        Ok(vec![
+            Event::BalanceDebited { account: "Bob".into(), amount: 2.into() },
+            Event::InvestmentCreated { id: 0.into(), owner: "Bob".into(), amount: 2.into() },
+        ])
+    );
+    assert_eq!(
+        bank_state.sell_investment("Bob".into(), 0.into()),
+        Ok(vec![
+            Event::InvestmentClosed { id: 0.into(), owner: "Bob".into(), amount: 2.into() },
+            Event::BalanceCredited { account: "Bob".into(), amount: 2.into() },
+        ])
+    );
+    assert_eq!(
+        bank_state.withdraw("Alice".into(), 7.into()),
+        Ok(vec![Event::BalanceDebited { account: "Alice".into(), amount: 7.into() }])
+    );
+}
+
+// balances, investments, allowances and schedules rebuilt from the events alone match the state
+#[test]
+fn events_replay_to_the_same_state() {
+    let mut rng        = StdRng::seed_from_u64(7);
+    let domains        = Domains::spec();
+    let mut bank_state = BankState::new();
+
+    let mut balances: BTreeMap<String, BigInt>           = BTreeMap::new();
+    let mut investments: BTreeMap<BigInt, (String, BigInt)> = BTreeMap::new();
+    let mut allowances: BTreeMap<(String, String), BigInt>  = BTreeMap::new();
+    let mut schedules: BTreeMap<BigInt, BigInt>             = BTreeMap::new();
+    let mut height                                          = BigInt::zero();
+
+    for _ in 0..2000 {
+        let Ok(events) = bank_state.apply(random_action(&mut rng, &domains, &bank_state.height)) else {
+            continue;
+        };
+
+        for event in events {
+            match event {
+                Event::BalanceCredited { account, amount } =>
+                    *balances.entry(account).or_insert_with(BigInt::zero) += amount,
+                Event::BalanceDebited { account, amount } =>
+                    *balances.entry(account).or_insert_with(BigInt::zero) -= amount,
+                Event::InvestmentCreated { id, owner, amount } => {
+                    investments.insert(id, (owner, amount));
+                }
+                Event::InvestmentClosed { id, .. } => {
+                    investments.remove(&id);
+                }
+                Event::AllowanceChanged { owner, spender, amount } => {
+                    allowances.insert((owner, spender), amount);
+                }
+                Event::TransferScheduled { id, at, .. } | Event::TransferRescheduled { id, at } => {
+                    schedules.insert(id, at);
+                }
+                Event::ScheduleEnded { id } => {
+                    schedules.remove(&id);
+                }
+                Event::HeightAdvanced { height: new_height } => height = new_height,
+                Event::TransferFailed { .. }
+                | Event::RateLimitSet { .. }
+                | Event::RateLimitUsed { .. }
+                | Event::AccountFrozen { .. }
+                | Event::AccountUnfrozen { .. } => {}
+            }
+        }
+    }
+
+    let expected: BTreeMap<_, _> = bank_state.balances.clone().into_iter().collect();
+    assert_eq!(balances, expected);
+
+    let expected: BTreeMap<_, _> = bank_state.investments
+        .iter()
+        .map(|(id, investment)| (id.clone(), (investment.owner.clone(), investment.amount.clone())))
+        .collect();
+    assert_eq!(investments, expected);
+
+    let expected: BTreeMap<_, _> = bank_state.allowances.clone().into_iter().collect();
+    assert_eq!(allowances, expected);
+
+    let expected: BTreeMap<_, _> = bank_state.schedules
+        .iter()
+        .map(|(id, schedule)| (id.clone(), schedule.at.clone()))
+        .collect();
+    assert_eq!(schedules, expected);
+    assert_eq!(height, bank_state.height);
+}
+
+#[test]
+fn events_round_trip_through_json() {
+    let event: Event<u64> = Event::InvestmentCreated { id: 3.into(), owner: "Alice".into(), amount: 5 };
+
+    let json = serde_json::to_string(&event).unwrap();
+    assert_eq!(json, r#"{"InvestmentCreated":{"id":"3","owner":"Alice","amount":"5"}}"#);
+    assert_eq!(serde_json::from_str::<Event<u64>>(&json).unwrap(), event);
+}
@@ -0,0 +1,30 @@
+use num_bigint::BigInt;
+use simple_bank::explorer::{explore, Domains, Node, INVARIANTS};
+
+#[test]
+fn bank_keeps_invariants_on_small_domains() {
+    let report = explore(&Domains::default(), 3, INVARIANTS);
+
+    assert!(report.violation.is_none(), "{:?}", report.violation);
+    assert_eq!(report.depth, 3);
+    assert!(report.states > 1);
+}
+
+fn alice_below_three(node: &Node) -> Result<(), String> {
+    match node.state.balances.get("Alice") {
+        Some(balance) if balance >= &BigInt::from(3) => Err(format!("Alice has {balance}")),
+        _ => Ok(()),
+    }
+}
+
+#[test]
+fn counterexamples_are_shortest() {
+    let report = explore(&Domains::default(), 5, &[("alice_below_three", alice_below_three)]);
+
+    let violation = report.violation.expect("Alice can reach 3 by depositing twice");
+    let trace: Vec<String> = violation.trace.iter().map(ToString::to_string).collect();
+
+    assert_eq!(violation.invariant, "alice_below_three");
+    assert_eq!(trace.len(), 2);
+    assert!(trace.iter().all(|action| action.starts_with("deposit Alice")));
+}
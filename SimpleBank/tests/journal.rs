@@ -0,0 +1,182 @@
+use num_bigint::BigInt;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use simple_bank::action::Action;
+use simple_bank::bank::BankState;
+use simple_bank::differential::random_action;
+use simple_bank::event::Event;
+use simple_bank::explorer::Domains;
+use simple_bank::journal::Journal;
+use simple_bank::projection::{Holders, InvestedPerOwner, Movement, Statements};
+
+// applies `actions` to a fresh state, journaling the successful ones
+fn run(actions: impl IntoIterator<Item = Action>) -> (BankState, Journal) {
+    let mut state   = BankState::new();
+    let mut journal = Journal::new();
+
+    for action in actions {
+        if let Ok(events) = state.apply(action.clone()) {
+            journal.record(action, events);
+        }
+    }
+
+    (state, journal)
+}
+
+fn actions(lines: &[&str]) -> Vec<Action> {
+    lines.iter().map(|line| line.parse().unwrap()).collect()
+}
+
+#[test]
+fn state_is_rebuilt_from_the_journal() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let domains = Domains::spec();
+
+    let mut state   = BankState::new();
+    let mut journal = Journal::new();
+
+    for _ in 0..2000 {
+        let action = random_action(&mut rng, &domains, &state.height);
+        if let Ok(events) = state.apply(action.clone()) {
+            journal.record(action, events);
+        }
+    }
+
+    assert!(state.height > BigInt::from(0));
+    assert!(!state.schedules.is_empty() || !state.failed_transfers.is_empty());
+    assert_eq!(journal.rebuild(), Ok(state));
+}
+
+#[test]
+fn journal_round_trips_through_the_action_log_format() {
+    let (state, journal) = run(actions(&[
+        "deposit Alice 10",
+        "buy_investment Alice 4",
+        "transfer Alice Bob 1",
+        "sell_investment Alice 0",
+    ]));
+
+    let log: String = journal.entries()
+        .iter()
+        .map(|entry| serde_json::to_string(entry).unwrap() + "\n")
+        .collect();
+
+    let read = Journal::read(log.as_bytes()).unwrap();
+    assert_eq!(read, journal);
+    assert_eq!(read.rebuild(), Ok(state));
+}
+
+#[test]
+fn action_logs_from_before_the_journal_are_still_read() {
+    // bare actions, amounts in num-bigint's own form
+    let log = concat!(
+        r#"{"Deposit":{"depositor":"Alice","amount":[1,[10]]}}"#, "\n",
+        r#"{"BuyInvestment":{"buyer":"Alice","amount":[1,[4]]}}"#, "\n",
+        r#"{"SellInvestment":{"seller":"Alice","investment_id":[0,[]]}}"#, "\n",
+    );
+
+    let (_, journal) = run(actions(&[
+        "deposit Alice 10",
+        "buy_investment Alice 4",
+        "sell_investment Alice 0",
+    ]));
+    assert_eq!(Journal::from_actions(log.as_bytes()), Ok(journal));
+
+    let withdraw = r#"{"Withdraw":{"withdrawer":"Alice","amount":[1,[11]]}}"#;
+    assert!(Journal::<BigInt>::from_actions(format!("{log}{withdraw}").as_bytes()).unwrap_err().starts_with("line 4:"));
+}
+
+#[test]
+fn freezes_are_journaled() {
+    let (state, journal) = run(actions(&[
+        "deposit Alice 10",
+        "freeze Alice",
+        "freeze Bob",
+        "unfreeze Alice",
+    ]));
+    assert!(!state.is_frozen("Alice"));
+    assert!(state.is_frozen("Bob"));
+
+    let log: String = journal.entries()
+        .iter()
+        .map(|entry| serde_json::to_string(entry).unwrap() + "\n")
+        .collect();
+
+    let read = Journal::read(log.as_bytes()).unwrap();
+    assert_eq!(read.entries().len(), 4);
+    assert_eq!(read.rebuild(), Ok(state));
+}
+
+#[test]
+fn inconsistent_journals_are_rejected() {
+    let mut journal = Journal::new();
+    journal.record(
+        "withdraw Alice 5".parse().unwrap(),
+        vec![Event::BalanceDebited { account: "Alice".into(), amount: 5.into() }],
+    );
+
+    assert!(journal.rebuild().unwrap_err().starts_with("Entry 0 (withdraw Alice 5)"));
+
+    // so do projections, with amounts which can underflow, rather than panicking
+    let mut bounded: Journal<u64> = Journal::default();
+    bounded.record(
+        "withdraw Alice 5".parse().unwrap(),
+        vec![Event::BalanceDebited { account: "Alice".into(), amount: 5 }],
+    );
+    let mut statements = Statements::default();
+    assert!(bounded.project(&mut statements).unwrap_err().starts_with("Entry 0 (withdraw Alice 5)"));
+}
+
+#[test]
+fn statements_list_every_movement_with_the_running_balance() {
+    let (_, journal) = run(actions(&[
+        "deposit Alice 10",
+        "transfer Alice Bob 3",
+        "withdraw Bob 5",
+        "buy_investment Alice 2",
+        "sell_investment Alice 0",
+    ]));
+
+    let mut statements = Statements::default();
+    journal.project(&mut statements).unwrap();
+
+    let alice: Vec<_> = statements.statement("Alice")
+        .iter()
+        .map(|line| (line.seq, line.movement.clone(), line.balance.clone()))
+        .collect();
+    assert_eq!(alice, vec![
+        (0, Movement::Credit(BigInt::from(10)), BigInt::from(10)),
+        (1, Movement::Debit(BigInt::from(3)), BigInt::from(7)),
+        (2, Movement::Debit(BigInt::from(2)), BigInt::from(5)),
+        (3, Movement::Credit(BigInt::from(2)), BigInt::from(7)),
+    ]);
+
+    // the failed withdrawal is not in the journal
+    assert_eq!(statements.statement("Bob").len(), 1);
+    assert!(statements.statement("Charlie").is_empty());
+}
+
+#[test]
+fn investments_and_holders_are_projected() {
+    let (state, journal) = run(actions(&[
+        "deposit Alice 10",
+        "deposit Bob 10",
+        "deposit Charlie 4",
+        "buy_investment Alice 3",
+        "buy_investment Alice 2",
+        "buy_investment Bob 1",
+        "sell_investment Bob 2",
+    ]));
+
+    let mut invested = InvestedPerOwner::default();
+    journal.project(&mut invested).unwrap();
+    assert_eq!(invested.invested("Alice"), BigInt::from(5));
+    assert_eq!(invested.invested("Bob"), BigInt::from(0));
+    assert_eq!(invested.totals().len(), 1);
+
+    let mut holders = Holders::default();
+    journal.project(&mut holders).unwrap();
+    let top: Vec<_> = holders.top(2).into_iter().map(|(account, _)| account).collect();
+    assert_eq!(top, vec!["Bob", "Alice"]);
+    assert_eq!(holders.top(10).len(), state.balances.len());
+}
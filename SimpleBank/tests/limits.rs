@@ -0,0 +1,176 @@
+use num_bigint::BigInt;
+use simple_bank::action::Action;
+use simple_bank::bank::{BankState, RateLimited, Spend};
+use simple_bank::event::Event;
+use simple_bank::journal::Journal;
+
+// Alice may move at most 5 per 3 blocks
+fn limited() -> BankState {
+    let mut bank_state = BankState::new();
+    bank_state.deposit("Alice".into(), 20.into()).unwrap();
+    bank_state.set_limit("Alice".into(), 5.into(), 3.into()).unwrap();
+    bank_state
+}
+
+fn rate_limited(available: i64, resets_at: Option<i64>) -> Result<Vec<Event>, String> {
+    Err(RateLimited { account: "Alice".to_string(), available: BigInt::from(available), resets_at: resets_at.map(BigInt::from) }.to_string())
+}
+
+#[test]
+fn debits_are_limited_within_the_window() {
+    let mut bank_state = limited();
+
+    assert_eq!(
+        bank_state.withdraw("Alice".into(), 2.into()),
+        Ok(vec![
+            Event::BalanceDebited { account: "Alice".into(), amount: 2.into() },
+            Event::RateLimitUsed { account: "Alice".into(), amount: 2.into() },
+        ])
+    );
+    bank_state.tick().unwrap();
+    assert!(bank_state.transfer("Alice".into(), "Bob".into(), 3.into()).is_ok());
+
+    // withdrawals, transfers and transfers from all count
+    bank_state.approve("Alice".into(), "Bob".into(), 10.into()).unwrap();
+    assert_eq!(bank_state.withdraw("Alice".into(), 1.into()), rate_limited(0, Some(4)));
+    assert_eq!(bank_state.transfer("Alice".into(), "Bob".into(), 1.into()), rate_limited(0, Some(4)));
+    assert_eq!(bank_state.transfer_from("Bob".into(), "Alice".into(), "Bob".into(), 1.into()), rate_limited(0, Some(4)));
+
+    // the failed debits left everything as is
+    assert_eq!(bank_state.balances["Alice"], BigInt::from(15));
+    assert_eq!(bank_state.allowance("Alice", "Bob"), BigInt::from(10));
+    assert_eq!(bank_state.window("Alice").len(), 2);
+}
+
+#[test]
+fn the_window_slides_with_the_height() {
+    let mut bank_state = limited();
+
+    bank_state.withdraw("Alice".into(), 2.into()).unwrap();
+    bank_state.tick().unwrap();
+    bank_state.withdraw("Alice".into(), 3.into()).unwrap();
+
+    bank_state.tick().unwrap();
+    assert_eq!(bank_state.withdraw("Alice".into(), 1.into()), rate_limited(0, Some(4)));
+
+    // the 2 withdrawn at height 0 expire at height 3, the 3 at height 1 only at 4
+    bank_state.tick().unwrap();
+    assert_eq!(bank_state.withdraw("Alice".into(), 3.into()), rate_limited(2, Some(4)));
+    assert!(bank_state.withdraw("Alice".into(), 2.into()).is_ok());
+
+    // expired spending is dropped once the account spends again
+    assert_eq!(bank_state.spent["Alice"].iter().cloned().collect::<Vec<_>>(), vec![
+        Spend { height: 1.into(), amount: 3.into() },
+        Spend { height: 3.into(), amount: 2.into() },
+    ]);
+}
+
+#[test]
+fn other_accounts_and_credits_are_not_limited() {
+    let mut bank_state = limited();
+    bank_state.deposit("Bob".into(), 20.into()).unwrap();
+
+    assert!(bank_state.withdraw("Bob".into(), 20.into()).is_ok());
+    assert!(bank_state.deposit("Alice".into(), 100.into()).is_ok());
+    assert!(bank_state.buy_investment("Alice".into(), 50.into()).is_ok());
+    assert!(!bank_state.spent.contains_key("Bob"));
+    assert!(bank_state.window("Alice").is_empty());
+}
+
+#[test]
+fn scheduled_transfers_are_limited_too() {
+    let mut bank_state = limited();
+    bank_state.schedule_transfer("Alice".into(), "Bob".into(), 4.into(), 1.into(), Some(1.into())).unwrap();
+
+    bank_state.tick().unwrap();
+    let events = bank_state.tick().unwrap();
+
+    assert_eq!(bank_state.balances["Bob"], BigInt::from(4));
+    assert_eq!(bank_state.failed_transfers.len(), 1);
+    assert!(bank_state.failed_transfers[0].reason.starts_with("Rate limited"));
+    assert!(events.iter().any(|event| matches!(event, Event::TransferFailed { .. })));
+}
+
+#[test]
+fn limits_are_replaced_and_validated() {
+    let mut bank_state = limited();
+    bank_state.withdraw("Alice".into(), 4.into()).unwrap();
+
+    // lowering the limit below what was spent blocks every debit until the window resets
+    bank_state.set_limit("Alice".into(), 2.into(), 3.into()).unwrap();
+    assert_eq!(bank_state.withdraw("Alice".into(), 1.into()), rate_limited(0, Some(3)));
+
+    // an amount above the limit never fits, the window being empty or not
+    bank_state.set_limit("Alice".into(), 0.into(), 1.into()).unwrap();
+    bank_state.tick().unwrap();
+    assert_eq!(bank_state.withdraw("Alice".into(), 1.into()), rate_limited(0, None));
+
+    assert_eq!(
+        bank_state.set_limit("Alice".into(), (-1).into(), 3.into()),
+        Err("Limit can't be negative".to_string())
+    );
+    assert_eq!(
+        bank_state.set_limit("Alice".into(), 5.into(), 0.into()),
+        Err("Window should be at least one block".to_string())
+    );
+    assert_eq!(bank_state.apply("set_limit Alice -1 3".parse().unwrap()), Err("Limit can't be negative".to_string()));
+}
+
+#[test]
+fn bounded_amounts_are_limited_too() {
+    let mut bank_state: BankState<u64> = BankState::default();
+    bank_state.deposit("Alice".into(), 20).unwrap();
+    bank_state.set_limit("Alice".into(), 5, 3.into()).unwrap();
+    bank_state.withdraw("Alice".into(), 4).unwrap();
+
+    bank_state.set_limit("Alice".into(), 2, 3.into()).unwrap();
+    assert_eq!(
+        bank_state.withdraw("Alice".into(), 1),
+        Err(RateLimited { account: "Alice".to_string(), available: 0u64, resets_at: Some(3.into()) }.to_string())
+    );
+}
+
+#[test]
+fn limits_are_rebuilt_from_the_journal() {
+    let mut bank_state = BankState::new();
+    let mut journal    = Journal::new();
+
+    for action in [
+        "deposit Alice 20",
+        "set_limit Alice 5 2",
+        "withdraw Alice 3",
+        "tick",
+        "transfer Alice Bob 2",
+        "tick",
+        "tick",
+        "withdraw Alice 4",
+    ] {
+        let action: Action = action.parse().unwrap();
+        let events         = bank_state.apply(action.clone()).unwrap();
+        journal.record(action, events);
+    }
+
+    assert_eq!(bank_state.spent["Alice"].len(), 1);
+    assert_eq!(journal.rebuild(), Ok(bank_state));
+}
+
+#[test]
+fn rate_limited_reports_when_the_window_resets() {
+    let limited = RateLimited { account: "Alice".to_string(), available: 2, resets_at: Some(7.into()) };
+    assert_eq!(limited.to_string(), "Rate limited: Alice may move up to 2 until the window resets at height 7");
+
+    let exceeded = RateLimited { account: "Alice".to_string(), available: 5, resets_at: None };
+    assert_eq!(exceeded.to_string(), "Rate limited: Alice may move up to 5, the amount exceeds the limit");
+}
+
+#[test]
+fn amounts_above_the_limit_never_reset() {
+    let mut bank_state = limited();
+
+    // nothing spent yet, but waiting wouldn't let 6 through either
+    assert_eq!(bank_state.withdraw("Alice".into(), 6.into()), rate_limited(5, None));
+
+    bank_state.withdraw("Alice".into(), 1.into()).unwrap();
+    assert_eq!(bank_state.withdraw("Alice".into(), 6.into()), rate_limited(4, None));
+    assert_eq!(bank_state.withdraw("Alice".into(), 5.into()), rate_limited(4, Some(3)));
+}
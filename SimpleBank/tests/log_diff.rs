@@ -0,0 +1,61 @@
+use serde_json::to_string;
+use simple_bank::bank::BankState;
+use simple_bank::log_diff::{first_divergence, Difference};
+
+fn log(states: &[BankState]) -> String {
+    states.iter().map(|s| to_string(s).unwrap() + "\n").collect()
+}
+
+#[test]
+fn same_state_serializes_identically_whatever_the_insertion_order() {
+    let mut left = BankState::new();
+    let mut right = BankState::new();
+
+    for account in ["Alice", "Bob", "Charlie", "Dave"] {
+        left.deposit(account.into(), 1.into()).unwrap();
+    }
+    for account in ["Dave", "Charlie", "Bob", "Alice"] {
+        right.deposit(account.into(), 1.into()).unwrap();
+    }
+
+    assert_eq!(to_string(&left).unwrap(), to_string(&right).unwrap());
+}
+
+#[test]
+fn reports_first_divergent_field() {
+    let mut state = BankState::new();
+    state.deposit("Alice".into(), 10.into()).unwrap();
+
+    let mut left = state.clone();
+    let mut right = state.clone();
+    left.transfer("Alice".into(), "Bob".into(), 3.into()).unwrap();
+    right.transfer("Alice".into(), "Bob".into(), 4.into()).unwrap();
+
+    let divergence = first_divergence(
+        log(&[state.clone(), left]).as_bytes(),
+        log(&[state, right]).as_bytes(),
+    )
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(divergence.line, 2);
+    assert_eq!(divergence.differences, vec![
+        Difference { path: "balances.Alice".into(), left: Some("7".into()), right: Some("6".into()) },
+        Difference { path: "balances.Bob".into(), left: Some("3".into()), right: Some("4".into()) },
+    ]);
+}
+
+#[test]
+fn shorter_log_diverges_where_it_ends() {
+    let state = BankState::new();
+
+    let divergence = first_divergence(
+        log(&[BankState::new()]).as_bytes(),
+        log(&[state.clone(), state]).as_bytes(),
+    )
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(divergence.line, 2);
+    assert_eq!(divergence.differences[0].left, None);
+}
@@ -0,0 +1,49 @@
+// Generated by `trace_to_test` from out1526.itf.json.
+
+use num_bigint::BigInt;
+use simple_bank::bank::BankState;
+
+#[test]
+fn invest_and_sell() {
+    let mut bank_state = BankState::new();
+    bank_state.balances.insert("Alice".into(), 0.into());
+    bank_state.balances.insert("Bob".into(), 0.into());
+    bank_state.balances.insert("Charlie".into(), 0.into());
+
+    // step 1
+    assert!(bank_state.sell_investment("Charlie".into(), 3.into()).is_err());
+
+    // step 2
+    assert!(bank_state.deposit("Bob".into(), (-41).into()).is_err());
+
+    // step 3
+    assert!(bank_state.deposit("Charlie".into(), 62.into()).is_ok());
+
+    // step 4
+    assert!(bank_state.transfer("Charlie".into(), "Alice".into(), 38.into()).is_ok());
+
+    // step 5
+    assert!(bank_state.transfer("Alice".into(), "Alice".into(), 67.into()).is_err());
+
+    // step 6
+    assert!(bank_state.buy_investment("Alice".into(), 18.into()).is_ok());
+
+    // step 7
+    assert!(bank_state.transfer("Alice".into(), "Bob".into(), 18.into()).is_ok());
+
+    // step 8
+    assert!(bank_state.buy_investment("Charlie".into(), (-46).into()).is_err());
+
+    // step 9
+    assert!(bank_state.deposit("Alice".into(), (-7).into()).is_err());
+
+    // step 10
+    assert!(bank_state.sell_investment("Alice".into(), 0.into()).is_ok());
+
+    assert_eq!(bank_state.balances.len(), 3);
+    assert_eq!(bank_state.balances["Alice"], BigInt::from(20));
+    assert_eq!(bank_state.balances["Bob"], BigInt::from(18));
+    assert_eq!(bank_state.balances["Charlie"], BigInt::from(24));
+    assert!(bank_state.investments.is_empty());
+    assert_eq!(bank_state.next_id, BigInt::from(1));
+}
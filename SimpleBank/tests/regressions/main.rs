@@ -0,0 +1,7 @@
+// Regression tests replaying failing MBT traces, generated with
+//
+//     cargo run --bin trace_to_test -- traces/out42.itf.json --out-dir tests/regressions
+//
+// Each test lives in its own module, declared below by the generator.
+
+mod invest_and_sell;
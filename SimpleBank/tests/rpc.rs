@@ -0,0 +1,185 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+struct Server {
+    child: Child,
+    addr:  String,
+}
+
+impl Server {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_simple_bank"))
+            .args(["--admin", "Root", "serve", "--addr", "127.0.0.1:0"])
+            .args(["--token", "Root=secret", "--token", "Alice=alice", "--token", "Bob=bob"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap()).read_line(&mut line).unwrap();
+        let addr = line.trim().strip_prefix("Listening on ").unwrap().to_string();
+
+        Server { child, addr }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    id:     u64,
+}
+
+impl Client {
+    // logs in with the token the test server gives `account`
+    fn connect(server: &Server, account: &str) -> Self {
+        let mut client = Client::anonymous(server);
+        let token = account.to_lowercase();
+        assert_eq!(client.call("login", json!({ "account": account, "token": token }))["result"], Value::Null);
+        client
+    }
+
+    fn anonymous(server: &Server) -> Self {
+        let writer = TcpStream::connect(&server.addr).unwrap();
+        Client { reader: BufReader::new(writer.try_clone().unwrap()), writer, id: 0 }
+    }
+
+    fn send(&mut self, line: &str) -> Value {
+        writeln!(self.writer, "{line}").unwrap();
+        let mut response = String::new();
+        self.reader.read_line(&mut response).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        let notification = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        writeln!(self.writer, "{notification}").unwrap();
+    }
+
+    fn call(&mut self, method: &str, params: Value) -> Value {
+        self.id += 1;
+        let request = json!({ "jsonrpc": "2.0", "id": self.id, "method": method, "params": params });
+        let response = self.send(&request.to_string());
+        assert_eq!(response["id"], self.id);
+        response
+    }
+}
+
+#[test]
+fn actions_are_shared_between_clients() {
+    let server    = Server::start();
+    let mut alice = Client::connect(&server, "Alice");
+    let mut bob   = Client::connect(&server, "Bob");
+
+    let response = alice.call("apply", json!({ "Deposit": { "depositor": "Alice", "amount": "10" } }));
+    assert_eq!(response["result"], json!([{ "BalanceCredited": { "account": "Alice", "amount": "10" } }]));
+    assert!(response.get("error").is_none());
+
+    let response = alice.call("apply", json!({ "BuyInvestment": { "buyer": "Alice", "amount": "4" } }));
+    assert_eq!(response["result"][1]["InvestmentCreated"]["id"], "0");
+
+    assert_eq!(bob.call("balance", json!({ "account": "Alice" }))["result"], "6");
+    assert_eq!(bob.call("balance", json!({ "account": "Bob" }))["result"], Value::Null);
+    assert_eq!(bob.call("investment", json!({ "id": "0" }))["result"]["owner"], "Alice");
+    assert_eq!(bob.call("state", Value::Null)["result"]["next_id"], "1");
+}
+
+#[test]
+fn errors_are_reported_with_their_code() {
+    let server     = Server::start();
+    let mut client = Client::connect(&server, "Bob");
+
+    let response = client.call("apply", json!({ "Withdraw": { "withdrawer": "Bob", "amount": "1" } }));
+    assert_eq!(response["error"]["code"], -32000);
+    assert!(response.get("result").is_none());
+
+    let response = client.call("apply", json!({ "Mint": { "amount": "1" } }));
+    assert_eq!(response["error"]["code"], -32602);
+
+    let response = client.call("close", Value::Null);
+    assert_eq!(response["error"]["code"], -32601);
+
+    let response = client.send("{ not json");
+    assert_eq!(response["error"]["code"], -32700);
+    assert_eq!(response["id"], Value::Null);
+}
+
+#[test]
+fn notifications_are_not_answered() {
+    let server     = Server::start();
+    let mut client = Client::connect(&server, "Alice");
+
+    client.notify("apply", json!({ "Deposit": { "depositor": "Alice", "amount": "10" } }));
+    client.notify("apply", json!({ "Withdraw": { "withdrawer": "Alice", "amount": "100" } }));
+    client.notify("close", Value::Null);
+
+    // the next line read answers the call, after the notifications were carried out
+    assert_eq!(client.call("balance", json!({ "account": "Alice" }))["result"], "10");
+
+    // while a null id is still a request
+    let response = client.send(r#"{"jsonrpc": "2.0", "id": null, "method": "balance", "params": {"account": "Alice"}}"#);
+    assert_eq!(response["id"], Value::Null);
+    assert_eq!(response["result"], "10");
+}
+
+#[test]
+fn callers_only_debit_what_they_are_allowed_to() {
+    let server    = Server::start();
+    let mut alice = Client::connect(&server, "Alice");
+    let mut bob   = Client::connect(&server, "Bob");
+    let mut root  = Client::anonymous(&server);
+    root.call("login", json!({ "account": "Root", "token": "secret" }));
+
+    alice.call("apply", json!({ "Deposit": { "depositor": "Alice", "amount": "10" } }));
+
+    let steal = json!({ "Transfer": { "sender": "Alice", "receiver": "Bob", "amount": "3" } });
+    let response = bob.call("apply", steal);
+    assert_eq!(response["error"]["code"], -32001);
+    assert_eq!(response["error"]["message"], "Unauthorized: Bob can't debit Alice");
+
+    let spend = json!({ "TransferFrom": { "spender": "Bob", "owner": "Alice", "receiver": "Bob", "amount": "3" } });
+    alice.call("apply", json!({ "Approve": { "owner": "Alice", "spender": "Bob", "amount": "3" } }));
+    assert_eq!(alice.call("apply", spend.clone())["error"]["code"], -32001);
+    assert!(bob.call("apply", spend.clone()).get("error").is_none());
+    // the allowance is used up, which is for the bank to say
+    assert_eq!(bob.call("apply", spend)["error"]["code"], -32000);
+
+    assert_eq!(alice.call("freeze", json!({ "account": "Bob" }))["error"]["code"], -32001);
+    assert!(root.call("freeze", json!({ "account": "Alice" })).get("error").is_none());
+    let response = alice.call("apply", json!({ "Withdraw": { "withdrawer": "Alice", "amount": "1" } }));
+    assert_eq!(response["error"]["message"], "Unauthorized: Alice is frozen");
+
+    alice.call("logout", Value::Null);
+    let response = alice.call("apply", json!({ "Deposit": { "depositor": "Alice", "amount": "1" } }));
+    assert_eq!(response["error"]["message"], "Unauthorized: not logged in");
+}
+
+#[test]
+fn logins_need_the_token_of_the_account() {
+    let server     = Server::start();
+    let mut client = Client::anonymous(&server);
+
+    for (account, token) in [("Root", "guess"), ("Root", "alice"), ("Root", ""), ("Carol", "carol")] {
+        let response = client.call("login", json!({ "account": account, "token": token }));
+        assert_eq!(response["error"]["code"], -32001);
+        assert_eq!(response["error"]["message"], format!("Unauthorized: bad credentials for {account}"));
+    }
+    // nor can the token be left out
+    let response = client.call("login", json!({ "account": "Alice" }));
+    assert_eq!(response["error"]["code"], -32602);
+
+    // still not logged in
+    let response = client.call("freeze", json!({ "account": "Bob" }));
+    assert_eq!(response["error"]["message"], "Unauthorized: not logged in");
+
+    assert_eq!(client.call("login", json!({ "account": "Root", "token": "secret" }))["result"], Value::Null);
+    assert!(client.call("freeze", json!({ "account": "Bob" })).get("error").is_none());
+}
@@ -0,0 +1,187 @@
+use num_bigint::BigInt;
+use simple_bank::action::Action;
+use simple_bank::bank::BankState;
+use simple_bank::event::Event;
+use simple_bank::journal::Journal;
+
+fn funded() -> BankState {
+    let mut bank_state = BankState::new();
+    bank_state.deposit("Alice".into(), 10.into()).unwrap();
+    bank_state
+}
+
+#[test]
+fn recurring_transfers_run_and_are_rescheduled() {
+    let mut bank_state = funded();
+
+    assert_eq!(
+        bank_state.schedule_transfer("Alice".into(), "Bob".into(), 3.into(), 2.into(), Some(2.into())),
+        Ok(vec![Event::TransferScheduled {
+            id:       0.into(),
+            sender:   "Alice".into(),
+            receiver: "Bob".into(),
+            amount:   3.into(),
+            at:       2.into(),
+            every:    Some(2.into()),
+        }])
+    );
+
+    assert_eq!(bank_state.tick(), Ok(vec![Event::HeightAdvanced { height: 1.into() }]));
+    assert_eq!(
+        bank_state.tick(),
+        Ok(vec![
+            Event::HeightAdvanced { height: 2.into() },
+            Event::BalanceDebited { account: "Alice".into(), amount: 3.into() },
+            Event::BalanceCredited { account: "Bob".into(), amount: 3.into() },
+            Event::TransferRescheduled { id: 0.into(), at: 4.into() },
+        ])
+    );
+
+    bank_state.tick().unwrap();
+    bank_state.tick().unwrap();
+
+    assert_eq!(bank_state.height, BigInt::from(4));
+    assert_eq!(bank_state.balances["Alice"], BigInt::from(4));
+    assert_eq!(bank_state.balances["Bob"], BigInt::from(6));
+    assert_eq!(bank_state.schedules[&BigInt::from(0)].at, BigInt::from(6));
+}
+
+#[test]
+fn one_off_transfers_end_after_running() {
+    let mut bank_state = funded();
+    bank_state.schedule_transfer("Alice".into(), "Bob".into(), 3.into(), 1.into(), None).unwrap();
+
+    assert_eq!(
+        bank_state.tick(),
+        Ok(vec![
+            Event::HeightAdvanced { height: 1.into() },
+            Event::BalanceDebited { account: "Alice".into(), amount: 3.into() },
+            Event::BalanceCredited { account: "Bob".into(), amount: 3.into() },
+            Event::ScheduleEnded { id: 0.into() },
+        ])
+    );
+    assert!(bank_state.schedules.is_empty());
+
+    bank_state.tick().unwrap();
+    assert_eq!(bank_state.balances["Bob"], BigInt::from(3));
+}
+
+#[test]
+fn failed_transfers_are_recorded_without_aborting_the_tick() {
+    let mut bank_state = funded();
+    bank_state.deposit("Bob".into(), 1.into()).unwrap();
+    bank_state.schedule_transfer("Bob".into(), "Alice".into(), 5.into(), 1.into(), None).unwrap();
+    bank_state.schedule_transfer("Alice".into(), "Bob".into(), 5.into(), 1.into(), Some(1.into())).unwrap();
+
+    let events = bank_state.tick().unwrap();
+    assert!(events.contains(&Event::TransferFailed {
+        id:     0.into(),
+        height: 1.into(),
+        reason: "Balance is too low".into(),
+    }));
+
+    assert_eq!(bank_state.failed_transfers.len(), 1);
+    assert_eq!(bank_state.failed_transfers[0].id, BigInt::from(0));
+    assert_eq!(bank_state.balances["Bob"], BigInt::from(6));
+
+    // a failing recurring transfer keeps its schedule
+    bank_state.tick().unwrap();
+    bank_state.tick().unwrap();
+    assert_eq!(bank_state.failed_transfers.len(), 2);
+    assert_eq!(bank_state.failed_transfers[1].height, BigInt::from(3));
+    assert_eq!(bank_state.schedules[&BigInt::from(1)].at, BigInt::from(4));
+}
+
+#[test]
+fn frozen_senders_are_not_debited() {
+    let mut bank_state = funded();
+    bank_state.schedule_transfer("Alice".into(), "Bob".into(), 3.into(), 1.into(), Some(1.into())).unwrap();
+    bank_state.freeze("Alice".into()).unwrap();
+
+    assert_eq!(
+        bank_state.tick(),
+        Ok(vec![
+            Event::HeightAdvanced { height: 1.into() },
+            Event::TransferFailed { id: 0.into(), height: 1.into(), reason: "Alice is frozen".into() },
+            Event::TransferRescheduled { id: 0.into(), at: 2.into() },
+        ])
+    );
+    assert_eq!(bank_state.balances["Alice"], BigInt::from(10));
+    assert_eq!(bank_state.failed_transfers.len(), 1);
+
+    bank_state.unfreeze("Alice".into()).unwrap();
+    bank_state.tick().unwrap();
+    assert_eq!(bank_state.balances["Alice"], BigInt::from(7));
+}
+
+#[test]
+fn due_transfers_run_in_the_order_they_were_scheduled() {
+    let mut bank_state = funded();
+    bank_state.schedule_transfer("Bob".into(), "Charlie".into(), 4.into(), 1.into(), None).unwrap();
+    bank_state.schedule_transfer("Alice".into(), "Bob".into(), 4.into(), 1.into(), None).unwrap();
+    bank_state.schedule_transfer("Bob".into(), "Charlie".into(), 4.into(), 1.into(), None).unwrap();
+
+    bank_state.tick().unwrap();
+
+    // the first one ran before Bob was funded, the last one after
+    assert_eq!(bank_state.failed_transfers.len(), 1);
+    assert_eq!(bank_state.failed_transfers[0].id, BigInt::from(0));
+    assert_eq!(bank_state.balances["Bob"], BigInt::from(0));
+    assert_eq!(bank_state.balances["Charlie"], BigInt::from(4));
+}
+
+#[test]
+fn invalid_schedules_are_rejected() {
+    let mut bank_state = funded();
+    bank_state.tick().unwrap();
+
+    assert_eq!(
+        bank_state.schedule_transfer("Alice".into(), "Bob".into(), 0.into(), 2.into(), None),
+        Err("Amount should be greater than zero".to_string())
+    );
+    assert_eq!(
+        bank_state.schedule_transfer("Alice".into(), "Bob".into(), 1.into(), 1.into(), None),
+        Err("Transfer should be scheduled in the future".to_string())
+    );
+    assert_eq!(
+        bank_state.schedule_transfer("Alice".into(), "Bob".into(), 1.into(), 2.into(), Some(0.into())),
+        Err("Period should be greater than zero".to_string())
+    );
+    assert!(bank_state.schedules.is_empty());
+    assert_eq!(bank_state.next_schedule_id, BigInt::from(0));
+}
+
+#[test]
+fn actions_parse_and_display() {
+    let action: Action = "schedule_transfer Alice Bob 3 5 2".parse().unwrap();
+    assert_eq!(action, Action::ScheduleTransfer {
+        sender:   "Alice".into(),
+        receiver: "Bob".into(),
+        amount:   3.into(),
+        at:       5.into(),
+        every:    Some(2.into()),
+    });
+    assert_eq!(action.to_string(), "schedule_transfer Alice Bob 3 5 2");
+
+    assert_eq!("schedule_transfer Alice Bob 3 5".parse::<Action>().unwrap().to_string(), "schedule_transfer Alice Bob 3 5");
+    assert_eq!("tick".parse::<Action>(), Ok(Action::Tick));
+    assert!("schedule_transfer Alice Bob 3 soon".parse::<Action>().is_err());
+}
+
+#[test]
+fn ticks_are_rebuilt_from_the_journal() {
+    let mut bank_state = funded();
+    let mut journal    = Journal::new();
+    journal.record(Action::Deposit { depositor: "Alice".into(), amount: 10.into() }, vec![
+        Event::BalanceCredited { account: "Alice".into(), amount: 10.into() },
+    ]);
+
+    for action in ["schedule_transfer Alice Bob 4 1 1", "schedule_transfer Bob Alice 1 2", "tick", "tick", "tick", "tick"] {
+        let action: Action = action.parse().unwrap();
+        let events         = bank_state.apply(action.clone()).unwrap();
+        journal.record(action, events);
+    }
+
+    assert_eq!(bank_state.failed_transfers.len(), 2);
+    assert_eq!(journal.rebuild(), Ok(bank_state));
+}
@@ -0,0 +1,63 @@
+use num_bigint::BigInt;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::thread;
+use simple_bank::action::Action;
+use simple_bank::bank::BankState;
+use simple_bank::service::BankService;
+
+const USERS: usize = 8;
+const CALLS: usize = 250;
+
+fn user(i: usize) -> String {
+    format!("user{i}")
+}
+
+#[test]
+fn concurrent_history_replays_to_the_same_state() {
+    let service = BankService::spawn(BankState::new());
+
+    let clients: Vec<_> = (0..USERS)
+        .map(|i| {
+            let service = service.clone();
+            thread::spawn(move || {
+                let mut rng = StdRng::seed_from_u64(i as u64);
+                for _ in 0..CALLS {
+                    let amount = BigInt::from(rng.gen_range(1..20));
+                    let action =
+                        match rng.gen_range(0..4) {
+                            0 => Action::Deposit { depositor: user(i), amount },
+                            1 => Action::Withdraw { withdrawer: user(i), amount },
+                            2 => Action::BuyInvestment { buyer: user(i), amount },
+                            _ => Action::Transfer {
+                                sender:   user(i),
+                                receiver: user(rng.gen_range(0..USERS)),
+                                amount,
+                            },
+                        };
+                    // failures (e.g. low balance) are part of the history too
+                    let _ = service.apply(action);
+                }
+            })
+        })
+        .collect();
+
+    for client in clients {
+        client.join().unwrap();
+    }
+
+    let history = service.history();
+    assert_eq!(history.entries.len(), USERS * CALLS);
+    assert_eq!(history.replay().unwrap(), service.state());
+}
+
+#[test]
+fn replay_detects_a_wrong_outcome() {
+    let service = BankService::spawn(BankState::new());
+    service.apply(Action::Deposit { depositor: "Alice".into(), amount: 5.into() }).unwrap();
+
+    let mut history = service.history();
+    history.entries[0].result = Err("Amount should be greater than zero".into());
+
+    assert!(history.replay().is_err());
+}
@@ -0,0 +1,110 @@
+use num_bigint::BigInt;
+use simple_bank::bank::{BankState, Change, Investment};
+
+#[test]
+fn snapshots_are_independent_and_diffable() {
+    let mut bank_state = BankState::new();
+    bank_state.deposit("Alice".into(), 10.into()).unwrap();
+    bank_state.deposit("Bob".into(), 5.into()).unwrap();
+
+    let snapshot = bank_state.clone();
+    bank_state.transfer("Alice".into(), "Charlie".into(), 4.into()).unwrap();
+    bank_state.buy_investment("Bob".into(), 5.into()).unwrap();
+
+    assert_eq!(snapshot.balances["Alice"], BigInt::from(10));
+    assert!(snapshot.investments.is_empty());
+
+    let investment = Investment { owner: "Bob".into(), amount: BigInt::from(5) };
+    let changes: Vec<_> = snapshot.diff(&bank_state).collect();
+    assert_eq!(changes, vec![
+        Change::Balance {
+            account: &"Alice".into(),
+            before:  Some(&BigInt::from(10)),
+            after:   Some(&BigInt::from(6)),
+        },
+        Change::Balance {
+            account: &"Bob".into(),
+            before:  Some(&BigInt::from(5)),
+            after:   Some(&BigInt::from(0)),
+        },
+        Change::Balance {
+            account: &"Charlie".into(),
+            before:  None,
+            after:   Some(&BigInt::from(4)),
+        },
+        Change::Investment {
+            id:     &BigInt::from(0),
+            before: None,
+            after:  Some(&investment),
+        },
+    ]);
+
+    assert_eq!(bank_state.diff(&bank_state.clone()).count(), 0);
+}
+
+#[test]
+fn diffs_cover_allowances_schedules_and_limits() {
+    let mut bank_state = BankState::new();
+    bank_state.deposit("Alice".into(), 10.into()).unwrap();
+
+    let snapshot = bank_state.clone();
+    bank_state.approve("Alice".into(), "Bob".into(), 3.into()).unwrap();
+    bank_state.set_limit("Alice".into(), 5.into(), 2.into()).unwrap();
+    bank_state.schedule_transfer("Bob".into(), "Alice".into(), 1.into(), 1.into(), None).unwrap();
+    bank_state.schedule_transfer("Alice".into(), "Bob".into(), 1.into(), 5.into(), Some(5.into())).unwrap();
+    bank_state.tick().unwrap();
+    bank_state.withdraw("Alice".into(), 2.into()).unwrap();
+
+    let changes: Vec<_> = snapshot.diff(&bank_state).collect();
+    assert!(changes.contains(&Change::Allowance {
+        owner_spender: &("Alice".into(), "Bob".into()),
+        before:        None,
+        after:         Some(&BigInt::from(3)),
+    }));
+    assert!(changes.contains(&Change::Height { before: &BigInt::from(0), after: &BigInt::from(1) }));
+    assert!(changes.contains(&Change::Schedule {
+        id:     &BigInt::from(1),
+        before: None,
+        after:  Some(&bank_state.schedules[&BigInt::from(1)]),
+    }));
+    // Bob has nothing, so the one-off transfer fails and its schedule is gone
+    assert!(changes.contains(&Change::FailedTransfer(&bank_state.failed_transfers[0])));
+    assert!(changes.contains(&Change::Limit {
+        account: &"Alice".into(),
+        before:  None,
+        after:   Some(&bank_state.limits["Alice"]),
+    }));
+    assert!(changes.contains(&Change::Spent {
+        account: &"Alice".into(),
+        before:  None,
+        after:   Some(&bank_state.spent["Alice"]),
+    }));
+    assert_eq!(changes.len(), 7, "{changes:?}");
+}
+
+#[test]
+fn failed_transfers_are_diffed_by_schedule_and_height() {
+    let mut bank_state = BankState::new();
+    bank_state.schedule_transfer("Bob".into(), "Alice".into(), 1.into(), 1.into(), None).unwrap();
+    bank_state.schedule_transfer("Carol".into(), "Alice".into(), 1.into(), 1.into(), None).unwrap();
+
+    // as many failures on both branches, but not the same ones
+    let mut bob_paid = bank_state.clone();
+    bob_paid.deposit("Bob".into(), 1.into()).unwrap();
+    bob_paid.tick().unwrap();
+    let mut carol_paid = bank_state;
+    carol_paid.deposit("Carol".into(), 1.into()).unwrap();
+    carol_paid.tick().unwrap();
+
+    let failures = |changes: Vec<Change<'_, BigInt>>| -> Vec<BigInt> {
+        changes.into_iter()
+            .filter_map(|change| match change {
+                Change::FailedTransfer(failure) => Some(failure.id.clone()),
+                _                               => None,
+            })
+            .collect()
+    };
+    assert_eq!(failures(bob_paid.diff(&carol_paid).collect()), vec![BigInt::from(0)]);
+    assert_eq!(failures(carol_paid.diff(&bob_paid).collect()), vec![BigInt::from(1)]);
+    assert_eq!(failures(bob_paid.diff(&bob_paid.clone()).collect()), vec![]);
+}
@@ -0,0 +1,45 @@
+use itf::trace_from_str;
+use num_bigint::BigInt;
+use serde::Deserialize;
+use simple_bank::trace_reader::TraceReader;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct Counter {
+    n:     BigInt,
+    label: String,
+}
+
+const TRACE: &str = r##"{
+  "#meta": { "format": "ITF", "source": "counter.qnt" },
+  "vars": [ "n", "label" ],
+  "states": [
+    { "#meta": { "index": 0 }, "n": { "#bigint": "0" }, "label": "start" },
+    { "#meta": { "index": 1 }, "n": { "#bigint": "1" }, "label": "a \"quoted\" ] label }" },
+    { "#meta": { "index": 2 }, "n": { "#bigint": "-2" }, "label": "" }
+  ],
+  "loop": null
+}"##;
+
+#[test]
+fn streams_same_states_as_trace_from_str() {
+    let expected: itf::Trace<Counter> = trace_from_str(TRACE).unwrap();
+
+    let mut reader = TraceReader::<_, Counter>::new(TRACE.as_bytes());
+    let states: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+
+    assert_eq!(states, expected.states);
+    assert_eq!(reader.vars(), ["n", "label"]);
+    assert_eq!(reader.meta().unwrap().source.as_deref(), Some("counter.qnt"));
+}
+
+#[test]
+fn stops_at_first_malformed_state() {
+    let trace = r##"{ "states": [ { "#meta": {}, "n": { "#bigint": "0" }, "label": "ok" }, { "n": "#bigint" "##;
+
+    let mut reader = TraceReader::<_, Counter>::new(trace.as_bytes());
+
+    let first = reader.next().unwrap().unwrap();
+    assert_eq!(first.value, Counter { n: 0.into(), label: "ok".to_string() });
+    assert!(reader.next().unwrap().is_err());
+    assert!(reader.next().is_none());
+}
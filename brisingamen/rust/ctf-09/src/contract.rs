@@ -1,28 +1,79 @@
-#[cfg(not(feature = "library"))]
+#[cfg(not(any(feature = "library", feature = "fixed")))]
 use cosmwasm_std::entry_point;
+use std::collections::BTreeMap;
+
 use cosmwasm_std::{
-    coin, to_binary, BankMsg, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, Uint128,
+    coin, to_binary, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Env, MessageInfo, Order,
+    Response, StdResult, Timestamp, Uint128,
 };
-use cw0::must_pay;
+use cw0::{must_pay, one_coin};
+use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+use semver::Version;
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{State, UserRewardInfo, STATE, USERS};
+use crate::migration;
+use crate::msg::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, OwnershipResponse, QueryMsg,
+    SimulateClaimResponse, TotalPendingRewardsResponse, UnbondingsResponse, UserEntry,
+    UsersResponse,
+};
+use crate::state::{
+    Reward, State, Unbonding, UserReward, UserRewardInfo, STATE, UNBONDINGS, USERS,
+};
+
+pub const CONTRACT_NAME: &str = "crates.io:oaksecurity-cosmwasm-ctf-09";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Users listed by default and at most
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
 
-pub const DENOM: &str = "uawesome";
-pub const REWARD_DENOM: &str = "uoak";
+/// How pending rewards are brought up to the global index
+pub type UpdateRewards = fn(&mut UserRewardInfo, &State);
 
-#[cfg_attr(not(feature = "library"), entry_point)]
+#[cfg_attr(not(any(feature = "library", feature = "fixed")), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    _msg: InstantiateMsg,
+    msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    let state = State {
-        owner: info.sender.clone(),
+    if msg.reward_denoms.is_empty() {
+        return Err(ContractError::NoRewardDenoms {});
+    }
+
+    // compounding stakes the rewards in the staked denom, which are
+    // otherwise mixed up with the stakes
+    let rewards_staked = msg.reward_denoms.contains(&msg.denom);
+    if msg.compounding && !rewards_staked {
+        return Err(ContractError::CompoundingDenoms {});
+    }
+    if !msg.compounding && rewards_staked {
+        return Err(ContractError::SameDenoms {});
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let reward = Reward {
         global_index: Decimal::zero(),
+        unallocated: Uint128::zero(),
+        rate: Uint128::zero(),
+        start: env.block.time,
+        end: env.block.time,
+    };
+    let state = State {
+        owner: Some(info.sender.clone()),
+        pending_owner: None,
+        denom: msg.denom,
+        compounding: msg.compounding,
+        unbonding_period: msg.unbonding_period,
+        rewards: msg
+            .reward_denoms
+            .into_iter()
+            .map(|denom| (denom, reward.clone()))
+            .collect(),
+        last_update: env.block.time,
         total_staked: Uint128::zero(),
     };
     STATE.save(deps.storage, &state)?;
@@ -32,7 +83,7 @@ pub fn instantiate(
         .add_attribute("owner", info.sender))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
+#[cfg_attr(not(any(feature = "library", feature = "fixed")), entry_point)]
 pub fn execute(
     deps: DepsMut,
     env: Env,
@@ -41,55 +92,142 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::IncreaseReward {} => increase_reward(deps, env, info),
-        ExecuteMsg::Deposit {} => deposit(deps, info),
-        ExecuteMsg::Withdraw { amount } => withdraw(deps, info, amount),
-        ExecuteMsg::ClaimRewards {} => claim_rewards(deps, info),
+        ExecuteMsg::StreamReward { rate, start, end } => {
+            stream_reward(deps, env, info, rate, start, end)
+        }
+        ExecuteMsg::Deposit {} => deposit(deps, env, info),
+        ExecuteMsg::Withdraw { amount } => withdraw(deps, env, info, amount),
+        ExecuteMsg::ClaimUnbonded {} => claim_unbonded(deps, env, info),
+        ExecuteMsg::ClaimRewards {} => claim_rewards(deps, env, info),
+        ExecuteMsg::ProposeOwner { owner } => propose_owner(deps, info, owner),
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::RenounceOwnership {} => renounce_ownership(deps, info),
     }
 }
 
+/// Converts the storage of an earlier version, refusing downgrades
+#[cfg_attr(not(any(feature = "library", feature = "fixed")), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let from = migration::stored_version(deps.storage, CONTRACT_NAME)?;
+    let to: Version = CONTRACT_VERSION.parse()?;
+
+    if from > to {
+        return Err(ContractError::CannotDowngrade {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+    }
+
+    migration::convert(deps.storage, &from)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", from.to_string())
+        .add_attribute("to_version", to.to_string()))
+}
+
 /// Entry point for owner to increase reward
 /// ANCHOR[id=increase_reward]
 pub fn increase_reward(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+
+    let funds = one_coin(&info).map_err(|_| ContractError::NoDenomSent {})?;
+
+    if state.owner.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let reward = state
+        .rewards
+        .get_mut(&funds.denom)
+        .ok_or(ContractError::UnknownRewardDenom {
+            denom: funds.denom.clone(),
+        })?;
+
+    // distributed right away, or carried until someone stakes
+    reward.unallocated += funds.amount;
+    accrue(&mut state, env.block.time);
+
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "increase_reward")
+        .add_attribute("denom", funds.denom))
+}
+
+/// Entry point for owner to stream rewards at `rate` per second from
+/// `start` to `end`, paying for all of them upfront
+pub fn stream_reward(
+    deps: DepsMut,
+    env: Env,
     info: MessageInfo,
+    rate: Uint128,
+    start: Timestamp,
+    end: Timestamp,
 ) -> Result<Response, ContractError> {
     let mut state = STATE.load(deps.storage)?;
 
-    let amount = must_pay(&info, REWARD_DENOM).map_err(|_| ContractError::NoDenomSent {})?;
+    let funds = one_coin(&info).map_err(|_| ContractError::NoDenomSent {})?;
 
-    if info.sender != state.owner {
+    if state.owner.as_ref() != Some(&info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
-    let total_stake = state.total_staked;
+    // release what the previous streams owe up to now
+    accrue(&mut state, env.block.time);
+
+    let reward = state
+        .rewards
+        .get_mut(&funds.denom)
+        .ok_or(ContractError::UnknownRewardDenom {
+            denom: funds.denom.clone(),
+        })?;
+
+    if reward.end > env.block.time {
+        return Err(ContractError::StreamRunning {});
+    }
+
+    if start < env.block.time || end <= start {
+        return Err(ContractError::InvalidStream {});
+    }
 
-    if total_stake.is_zero() {
-        // No need to distribute rewards if no one staked
-        return Err(ContractError::NoUserStake {});
+    let expected = rate
+        .checked_mul(Uint128::from(end.seconds() - start.seconds()))
+        .map_err(|_| ContractError::StreamOverflow {})?;
+    if funds.amount != expected {
+        return Err(ContractError::StreamFunds { expected });
     }
 
-    state.global_index += Decimal::from_ratio(amount, total_stake);
+    reward.rate = rate;
+    reward.start = start;
+    reward.end = end;
 
     STATE.save(deps.storage, &state)?;
 
-    Ok(Response::new().add_attribute("action", "increase_reward"))
+    Ok(Response::new()
+        .add_attribute("action", "stream_reward")
+        .add_attribute("denom", funds.denom)
+        .add_attribute("rate", rate)
+        .add_attribute("start", start.seconds().to_string())
+        .add_attribute("end", end.seconds().to_string()))
 }
 
 /// Entry point for users to deposit funds
 /// ANCHOR[id=deposit]
-pub fn deposit(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
-    let amount = must_pay(&info, DENOM).map_err(|_| ContractError::NoDenomSent {})?;
-
+pub fn deposit(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
     let mut state = STATE.load(deps.storage)?;
+    accrue(&mut state, env.block.time);
+
+    let amount = must_pay(&info, &state.denom).map_err(|_| ContractError::NoDenomSent {})?;
 
     let mut user = USERS
         .load(deps.storage, &info.sender)
-        .unwrap_or(UserRewardInfo {
-            staked_amount: Uint128::zero(),
-            user_index: state.global_index,
-            pending_rewards: Uint128::zero(),
-        });
+        .unwrap_or_else(|_| new_user(&state));
 
     // update rewards
     update_rewards(&mut user, &state);
@@ -110,10 +248,12 @@ pub fn deposit(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractErr
 /// ANCHOR[id=withdraw]
 pub fn withdraw(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
     let mut state = STATE.load(deps.storage)?;
+    accrue(&mut state, env.block.time);
 
     let mut user = USERS.load(deps.storage, &info.sender)?;
 
@@ -137,9 +277,23 @@ pub fn withdraw(
     USERS.save(deps.storage, &info.sender, &user)?;
     STATE.save(deps.storage, &state)?;
 
+    // unbond the amount, which no longer earns rewards
+    if state.unbonding_period > 0 {
+        let release_at = env.block.time.plus_seconds(state.unbonding_period);
+        let mut unbondings = UNBONDINGS
+            .may_load(deps.storage, &info.sender)?
+            .unwrap_or_default();
+        unbondings.push(Unbonding { amount, release_at });
+        UNBONDINGS.save(deps.storage, &info.sender, &unbondings)?;
+
+        return Ok(Response::new()
+            .add_attribute("action", "withdraw")
+            .add_attribute("release_at", release_at.seconds().to_string()));
+    }
+
     let msg = BankMsg::Send {
         to_address: info.sender.to_string(),
-        amount: vec![coin(amount.u128(), DENOM)],
+        amount: vec![coin(amount.u128(), &state.denom)],
     };
 
     Ok(Response::new()
@@ -147,36 +301,185 @@ pub fn withdraw(
         .add_message(msg))
 }
 
+/// Entry point for users to claim the withdrawn funds done unbonding
+pub fn claim_unbonded(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+
+    let (released, unbonding): (Vec<_>, Vec<_>) = UNBONDINGS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default()
+        .into_iter()
+        .partition(|unbonding| unbonding.release_at <= env.block.time);
+
+    let amount: Uint128 = released.iter().map(|unbonding| unbonding.amount).sum();
+    if amount.is_zero() {
+        return Err(ContractError::NothingUnbonded {});
+    }
+
+    if unbonding.is_empty() {
+        UNBONDINGS.remove(deps.storage, &info.sender);
+    } else {
+        UNBONDINGS.save(deps.storage, &info.sender, &unbonding)?;
+    }
+
+    let msg = BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![coin(amount.u128(), state.denom)],
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "claim_unbonded")
+        .add_attribute("amount", amount)
+        .add_message(msg))
+}
+
 /// Entry point for user to claim rewards
 /// ANCHOR[id=claim_rewards]
-pub fn claim_rewards(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+pub fn claim_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
     let mut user = USERS.load(deps.storage, &info.sender)?;
 
-    let state = STATE.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    accrue(&mut state, env.block.time);
 
     // update rewards
     update_rewards(&mut user, &state);
 
-    let amount = user.pending_rewards;
+    // take the pending rewards of every denom
+    let mut claimed: Vec<Coin> = vec![];
+    for (denom, user_reward) in user.rewards.iter_mut() {
+        if !user_reward.pending_rewards.is_zero() {
+            claimed.push(coin(user_reward.pending_rewards.u128(), denom));
+            user_reward.pending_rewards = Uint128::zero();
+        }
+    }
 
     // disallow claiming zero rewards
-    if amount.is_zero() {
+    if claimed.is_empty() {
         return Err(ContractError::ZeroRewardClaim {});
     }
 
-    // set pending rewards to zero
-    user.pending_rewards = Uint128::zero();
+    let mut response = Response::new().add_attribute("action", "claim_reward");
+
+    // stake the rewards in the staked denom, which the contract already holds
+    if state.compounding {
+        if let Some(i) = claimed.iter().position(|funds| funds.denom == state.denom) {
+            let amount = claimed.remove(i).amount;
+            user.staked_amount += amount;
+            state.total_staked += amount;
+            response = response.add_attribute("compounded", amount);
+        }
+    }
 
     USERS.save(deps.storage, &info.sender, &user)?;
+    STATE.save(deps.storage, &state)?;
 
-    let msg = BankMsg::Send {
-        to_address: info.sender.to_string(),
-        amount: vec![coin(amount.u128(), REWARD_DENOM)],
-    };
+    // pay out the others at once
+    if !claimed.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: claimed,
+        });
+    }
+
+    Ok(response)
+}
+
+/// Entry point for owner to propose a new owner
+pub fn propose_owner(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: String,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+
+    if state.owner.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pending_owner = deps.api.addr_validate(&owner)?;
+    state.pending_owner = Some(pending_owner.clone());
+
+    STATE.save(deps.storage, &state)?;
 
     Ok(Response::new()
-        .add_attribute("action", "claim_reward")
-        .add_message(msg))
+        .add_attribute("action", "propose_owner")
+        .add_attribute("pending_owner", pending_owner))
+}
+
+/// Entry point for the pending owner to take over
+pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+
+    match &state.pending_owner {
+        None => return Err(ContractError::NoPendingOwner {}),
+        Some(pending_owner) if *pending_owner != info.sender => {
+            return Err(ContractError::Unauthorized {})
+        }
+        Some(_) => {}
+    }
+
+    state.owner = state.pending_owner.take();
+
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("owner", info.sender))
+}
+
+/// Entry point for owner to give up the ownership, along with any proposal
+pub fn renounce_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+
+    if state.owner.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    state.owner = None;
+    state.pending_owner = None;
+
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new().add_attribute("action", "renounce_ownership"))
+}
+
+/// Streams the rewards due by `now` and adds the unallocated ones to the
+/// global index, carrying them over while nobody is staked
+/// ANCHOR[id=accrue]
+pub fn accrue(state: &mut State, now: Timestamp) {
+    for reward in state.rewards.values_mut() {
+        let from = state.last_update.max(reward.start);
+        let to = now.min(reward.end);
+        if to > from {
+            reward.unallocated += reward.rate * Uint128::from(to.seconds() - from.seconds());
+        }
+
+        if !state.total_staked.is_zero() && !reward.unallocated.is_zero() {
+            reward.global_index += Decimal::from_ratio(reward.unallocated, state.total_staked);
+            reward.unallocated = Uint128::zero();
+        }
+    }
+    state.last_update = state.last_update.max(now);
+}
+
+/// A user with nothing staked yet, earning from the current indexes
+fn new_user(state: &State) -> UserRewardInfo {
+    UserRewardInfo {
+        staked_amount: Uint128::zero(),
+        rewards: state
+            .rewards
+            .iter()
+            .map(|(denom, reward)| (denom.clone(), UserReward::starting_at(reward)))
+            .collect(),
+    }
 }
 
 /// ANCHOR[id=update_rewards]
@@ -186,32 +489,179 @@ pub fn update_rewards(user: &mut UserRewardInfo, state: &State) {
         return;
     }
 
-    // calculate pending rewards
-    let reward = (state.global_index - user.user_index) * user.staked_amount;
-    user.pending_rewards += reward;
+    for (denom, reward) in &state.rewards {
+        let user_reward = user
+            .rewards
+            .entry(denom.clone())
+            .or_insert_with(|| UserReward::starting_at(reward));
 
-    user.user_index = state.global_index;
+        // calculate pending rewards
+        let pending = (reward.global_index - user_reward.user_index) * user.staked_amount;
+        user_reward.pending_rewards += pending;
+
+        user_reward.user_index = reward.global_index;
+    }
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+#[cfg_attr(not(any(feature = "library", feature = "fixed")), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::State {} => to_binary(&query_state(deps)?),
-        QueryMsg::User { user } => to_binary(&query_user(deps, user)?),
+        QueryMsg::State {} => to_binary(&query_state(deps, env)?),
+        QueryMsg::User { user } => to_binary(&query_user(deps, env, user)?),
+        QueryMsg::Users { start_after, limit } => {
+            to_binary(&query_users(deps, env, start_after, limit)?)
+        }
+        QueryMsg::SimulateClaim { user } => to_binary(&query_simulate_claim(deps, env, user)?),
+        QueryMsg::TotalPendingRewards {} => to_binary(&query_total_pending_rewards(deps, env)?),
+        QueryMsg::Unbondings { user } => to_binary(&query_unbondings(deps, user)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Ownership {} => to_binary(&query_ownership(deps)?),
     }
 }
 
-/// Query contract state
-pub fn query_state(deps: Deps) -> StdResult<State> {
-    let state = STATE.load(deps.storage)?;
+/// Query contract state, accrued up to now
+pub fn query_state(deps: Deps, env: Env) -> StdResult<State> {
+    let mut state = STATE.load(deps.storage)?;
+    accrue(&mut state, env.block.time);
     Ok(state)
 }
 
-/// Query user information
-pub fn query_user(deps: Deps, user: String) -> StdResult<UserRewardInfo> {
-    let user = deps.api.addr_validate(&user).unwrap();
+/// Query the funds a user is unbonding, none for unknown users
+pub fn query_unbondings(deps: Deps, user: String) -> StdResult<UnbondingsResponse> {
+    let user = deps.api.addr_validate(&user)?;
+    let unbondings = UNBONDINGS
+        .may_load(deps.storage, &user)?
+        .unwrap_or_default();
+    Ok(UnbondingsResponse { unbondings })
+}
+
+/// Query the denoms and unbonding period of the pool
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let state = STATE.load(deps.storage)?;
-    let mut user_info = USERS.load(deps.storage, &user)?;
-    update_rewards(&mut user_info, &state);
+    Ok(ConfigResponse {
+        denom: state.denom,
+        reward_denoms: state.rewards.into_keys().collect(),
+        compounding: state.compounding,
+        unbonding_period: state.unbonding_period,
+    })
+}
+
+/// Query the current and pending owner
+pub fn query_ownership(deps: Deps) -> StdResult<OwnershipResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(OwnershipResponse {
+        owner: state.owner,
+        pending_owner: state.pending_owner,
+    })
+}
+
+/// Query user information, accrued up to now
+pub fn query_user(deps: Deps, env: Env, user: String) -> StdResult<UserRewardInfo> {
+    let state = query_state(deps, env)?;
+    user_info(deps, &state, user, update_rewards)
+}
+
+/// Information of `user` updated with `update` against `state`, as if just
+/// arrived if unknown
+pub fn user_info(
+    deps: Deps,
+    state: &State,
+    user: String,
+    update: UpdateRewards,
+) -> StdResult<UserRewardInfo> {
+    let user = deps.api.addr_validate(&user)?;
+    let mut user_info = USERS
+        .may_load(deps.storage, &user)?
+        .unwrap_or_else(|| new_user(state));
+    update(&mut user_info, state);
     Ok(user_info)
 }
+
+/// Query the information of users after `start_after`, accrued up to now
+pub fn query_users(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<UsersResponse> {
+    users_page(deps, env, start_after, limit, update_rewards)
+}
+
+/// Information of up to `limit` users after `start_after`, updated with
+/// `update`
+pub fn users_page(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    update: UpdateRewards,
+) -> StdResult<UsersResponse> {
+    let state = query_state(deps, env)?;
+    let start_after = start_after
+        .map(|user| deps.api.addr_validate(&user))
+        .transpose()?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let users = USERS
+        .range(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            let (address, mut info) = item?;
+            update(&mut info, &state);
+            Ok(UserEntry { address, info })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(UsersResponse { users })
+}
+
+/// Query the rewards `user` would claim now
+pub fn query_simulate_claim(
+    deps: Deps,
+    env: Env,
+    user: String,
+) -> StdResult<SimulateClaimResponse> {
+    let state = query_state(deps, env)?;
+    let info = user_info(deps, &state, user, update_rewards)?;
+    let rewards = state
+        .rewards
+        .keys()
+        .map(|denom| {
+            let amount = info
+                .rewards
+                .get(denom)
+                .map(|user_reward| user_reward.pending_rewards)
+                .unwrap_or_default();
+            coin(amount.u128(), denom)
+        })
+        .collect();
+    Ok(SimulateClaimResponse { rewards })
+}
+
+/// Query the rewards owed to all users, accrued up to now
+pub fn query_total_pending_rewards(deps: Deps, env: Env) -> StdResult<TotalPendingRewardsResponse> {
+    let state = query_state(deps, env)?;
+    let mut totals: BTreeMap<&str, Uint128> = state
+        .rewards
+        .keys()
+        .map(|denom| (denom.as_str(), Uint128::zero()))
+        .collect();
+    for item in USERS.range(deps.storage, None, None, Order::Ascending) {
+        let (_, mut user_info) = item?;
+        update_rewards(&mut user_info, &state);
+        for (denom, user_reward) in &user_info.rewards {
+            if let Some(total) = totals.get_mut(denom.as_str()) {
+                *total += user_reward.pending_rewards;
+            }
+        }
+    }
+    let rewards = totals
+        .into_iter()
+        .map(|(denom, amount)| coin(amount.u128(), denom))
+        .collect();
+    Ok(TotalPendingRewardsResponse { rewards })
+}
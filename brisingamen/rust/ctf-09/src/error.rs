@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +6,9 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Version(#[from] semver::Error),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -21,6 +24,39 @@ pub enum ContractError {
     #[error("Caller did not provide requested funds")]
     NoDenomSent {},
 
-    #[error("No user staked")]
-    NoUserStake {},
+    #[error("{denom} is not a reward denom")]
+    UnknownRewardDenom { denom: String },
+
+    #[error("At least one reward denom is needed")]
+    NoRewardDenoms {},
+
+    #[error("No unbonded funds to claim")]
+    NothingUnbonded {},
+
+    #[error("A reward stream is still running")]
+    StreamRunning {},
+
+    #[error("Reward stream should end after it starts, which is not in the past")]
+    InvalidStream {},
+
+    #[error("Reward stream needs {expected} to be sent along")]
+    StreamFunds { expected: Uint128 },
+
+    #[error("Reward stream pays out more than can be held")]
+    StreamOverflow {},
+
+    #[error("Stake and reward denoms should differ")]
+    SameDenoms {},
+
+    #[error("Compounding needs rewards in the staked denom")]
+    CompoundingDenoms {},
+
+    #[error("No owner was proposed")]
+    NoPendingOwner {},
+
+    #[error("Cannot migrate from contract {contract}")]
+    WrongContract { contract: String },
+
+    #[error("Cannot downgrade from {from} to {to}")]
+    CannotDowngrade { from: String, to: String },
 }
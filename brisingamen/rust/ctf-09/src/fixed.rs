@@ -0,0 +1,120 @@
+//! The contract with the re-entry reward bug closed, as in `brisingamenTwin.qnt`.
+//!
+//! `contract::update_rewards` leaves `user_index` behind while a user has
+//! nothing staked, so a user who withdraws everything and deposits again is
+//! paid for the rewards distributed in between. Here the index of the sender
+//! is always moved to `global_index` before handing over to the original
+//! entry points, which then have nothing left to pay for the absence.
+//!
+//! Build with `--features fixed` to export these entry points instead.
+#[cfg(all(feature = "fixed", not(feature = "library")))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage,
+};
+
+use crate::contract;
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{State, UserReward, UserRewardInfo, STATE, USERS};
+
+#[cfg_attr(all(feature = "fixed", not(feature = "library")), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    contract::instantiate(deps, env, info, msg)
+}
+
+#[cfg_attr(all(feature = "fixed", not(feature = "library")), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    contract::migrate(deps, env, msg)
+}
+
+#[cfg_attr(all(feature = "fixed", not(feature = "library")), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::IncreaseReward {} => contract::increase_reward(deps, env, info),
+        ExecuteMsg::StreamReward { rate, start, end } => {
+            contract::stream_reward(deps, env, info, rate, start, end)
+        }
+        ExecuteMsg::Deposit {} => {
+            sync_user(deps.storage, &env, &info)?;
+            contract::deposit(deps, env, info)
+        }
+        ExecuteMsg::Withdraw { amount } => {
+            sync_user(deps.storage, &env, &info)?;
+            contract::withdraw(deps, env, info, amount)
+        }
+        ExecuteMsg::ClaimUnbonded {} => contract::claim_unbonded(deps, env, info),
+        ExecuteMsg::ClaimRewards {} => {
+            sync_user(deps.storage, &env, &info)?;
+            contract::claim_rewards(deps, env, info)
+        }
+        ExecuteMsg::ProposeOwner { owner } => contract::propose_owner(deps, info, owner),
+        ExecuteMsg::AcceptOwnership {} => contract::accept_ownership(deps, info),
+        ExecuteMsg::RenounceOwnership {} => contract::renounce_ownership(deps, info),
+    }
+}
+
+/// Brings the rewards of the sender, if known, up to the global index as
+/// accrued by now
+fn sync_user(
+    storage: &mut dyn Storage,
+    env: &Env,
+    info: &MessageInfo,
+) -> Result<(), ContractError> {
+    if let Some(mut user) = USERS.may_load(storage, &info.sender)? {
+        let mut state = STATE.load(storage)?;
+        contract::accrue(&mut state, env.block.time);
+        update_rewards(&mut user, &state);
+        STATE.save(storage, &state)?;
+        USERS.save(storage, &info.sender, &user)?;
+    }
+    Ok(())
+}
+
+/// ANCHOR[id=update_rewards]
+pub fn update_rewards(user: &mut UserRewardInfo, state: &State) {
+    for (denom, reward) in &state.rewards {
+        let user_reward = user
+            .rewards
+            .entry(denom.clone())
+            .or_insert_with(|| UserReward::starting_at(reward));
+
+        // calculate pending rewards, which are zero without stake
+        let pending = (reward.global_index - user_reward.user_index) * user.staked_amount;
+        user_reward.pending_rewards += pending;
+
+        // always sync the index, so that a later stake starts from here
+        user_reward.user_index = reward.global_index;
+    }
+}
+
+#[cfg_attr(all(feature = "fixed", not(feature = "library")), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::User { user } => to_binary(&query_user(deps, env, user)?),
+        QueryMsg::Users { start_after, limit } => to_binary(&contract::users_page(
+            deps,
+            env,
+            start_after,
+            limit,
+            update_rewards,
+        )?),
+        _ => contract::query(deps, env, msg),
+    }
+}
+
+/// Query user information, accrued up to now
+pub fn query_user(deps: Deps, env: Env, user: String) -> StdResult<UserRewardInfo> {
+    let state = contract::query_state(deps, env)?;
+    contract::user_info(deps, &state, user, update_rewards)
+}
@@ -1,19 +1,43 @@
 #[cfg(test)]
 pub mod tests {
     use crate::{
-        contract::{DENOM, REWARD_DENOM},
-        msg::{ExecuteMsg, InstantiateMsg, QueryMsg},
-        state::{State, UserRewardInfo},
+        contract::{CONTRACT_NAME, CONTRACT_VERSION},
+        migration::{convert, v0_1},
+        msg::{
+            ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, OwnershipResponse, QueryMsg,
+            SimulateClaimResponse, TotalPendingRewardsResponse, UnbondingsResponse, UserEntry,
+            UsersResponse,
+        },
+        state::{Reward, State, Unbonding, UserReward, UserRewardInfo, STATE, USERS},
+        ContractError,
     };
-    use cosmwasm_std::{coin, Addr, Decimal, Empty, Uint128};
-    use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+    use std::collections::BTreeMap;
+
+    use cosmwasm_std::{
+        coin, testing::MockStorage, Addr, Coin, Decimal, DepsMut, Empty, Env, Event, MessageInfo,
+        Response, StdResult, Storage, Timestamp, Uint128,
+    };
+    use cw_multi_test::{App, AppResponse, BankSudo, Contract, ContractWrapper, Executor, SudoMsg};
+    use semver::Version;
 
     pub fn challenge_contract() -> Box<dyn Contract<Empty>> {
         let contract = ContractWrapper::new(
             crate::contract::execute,
             crate::contract::instantiate,
             crate::contract::query,
-        );
+        )
+        .with_migrate(crate::contract::migrate);
+        Box::new(contract)
+    }
+
+    /// The contract with the re-entry reward bug closed
+    pub fn fixed_contract() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            crate::fixed::execute,
+            crate::fixed::instantiate,
+            crate::fixed::query,
+        )
+        .with_migrate(crate::fixed::migrate);
         Box::new(contract)
     }
 
@@ -21,12 +45,69 @@ pub mod tests {
     pub const USER: &str = "user";
     pub const USER2: &str = "user2";
 
+    pub const DENOM: &str = "uawesome";
+    pub const REWARD_DENOM: &str = "uoak";
+    pub const BONUS_DENOM: &str = "ubonus";
+
+    /// Instantiates the pool of the challenge
+    pub fn instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            denom: DENOM.to_string(),
+            reward_denoms: vec![REWARD_DENOM.to_string()],
+            compounding: false,
+            unbonding_period: 0,
+        }
+    }
+
+    /// Rewards in `denom`, with nothing streamed from `start` to `end`
+    pub fn unstreamed(
+        denom: &str,
+        global_index: Decimal,
+        start: Timestamp,
+        end: Timestamp,
+    ) -> BTreeMap<String, Reward> {
+        let reward = Reward {
+            global_index,
+            unallocated: Uint128::zero(),
+            rate: Uint128::zero(),
+            start,
+            end,
+        };
+        BTreeMap::from([(denom.to_string(), reward)])
+    }
+
+    /// Rewards of a user in `denom` only
+    pub fn user_rewards(
+        denom: &str,
+        user_index: Decimal,
+        pending_rewards: Uint128,
+    ) -> BTreeMap<String, UserReward> {
+        let reward = UserReward {
+            user_index,
+            pending_rewards,
+        };
+        BTreeMap::from([(denom.to_string(), reward)])
+    }
+
+    /// Rewards in `denom` pending for `user`
+    pub fn pending(user: &UserRewardInfo, denom: &str) -> Uint128 {
+        user.rewards
+            .get(denom)
+            .map(|reward| reward.pending_rewards)
+            .unwrap_or_default()
+    }
+
     pub fn proper_instantiate() -> (App, Addr) {
+        instantiate_contract(challenge_contract())
+    }
+
+    /// Sets up the base scenario with `contract`
+    pub fn instantiate_contract(contract: Box<dyn Contract<Empty>>) -> (App, Addr) {
         let mut app = App::default();
-        let cw_template_id = app.store_code(challenge_contract());
+        let cw_template_id = app.store_code(contract);
 
         // init contract
-        let msg = InstantiateMsg {};
+        let msg = instantiate_msg();
         let contract_addr = app
             .instantiate_contract(
                 cw_template_id,
@@ -34,7 +115,7 @@ pub mod tests {
                 &msg,
                 &[],
                 "test",
-                None,
+                Some(OWNER.to_string()),
             )
             .unwrap();
 
@@ -44,12 +125,19 @@ pub mod tests {
             .query_wasm_smart(contract_addr.clone(), &QueryMsg::State {})
             .unwrap();
 
+        // nothing is streamed until the owner increases the rewards
+        let now = app.block_info().time;
         assert_eq!(
             state,
             State {
-                owner: Addr::unchecked(OWNER),
+                owner: Some(Addr::unchecked(OWNER)),
+                pending_owner: None,
+                denom: DENOM.to_string(),
+                compounding: false,
+                unbonding_period: 0,
+                rewards: unstreamed(REWARD_DENOM, Decimal::zero(), now, now),
+                last_update: now,
                 total_staked: Uint128::zero(),
-                global_index: Decimal::zero()
             }
         );
 
@@ -78,15 +166,6 @@ pub mod tests {
         )
         .unwrap_err();
 
-        // cannot increase reward if no one staked
-        app.execute_contract(
-            Addr::unchecked(OWNER),
-            contract_addr.clone(),
-            &ExecuteMsg::IncreaseReward {},
-            &[coin(10_000, REWARD_DENOM)],
-        )
-        .unwrap_err();
-
         // cannot deposit reward denom
         app.execute_contract(
             Addr::unchecked(USER),
@@ -123,9 +202,14 @@ pub mod tests {
         assert_eq!(
             state,
             State {
-                owner: Addr::unchecked(OWNER),
+                owner: Some(Addr::unchecked(OWNER)),
+                pending_owner: None,
+                denom: DENOM.to_string(),
+                compounding: false,
+                unbonding_period: 0,
+                rewards: unstreamed(REWARD_DENOM, Decimal::one(), now, now),
+                last_update: now,
                 total_staked: Uint128::new(10_000),
-                global_index: Decimal::one()
             }
         );
 
@@ -144,8 +228,7 @@ pub mod tests {
             user_info,
             UserRewardInfo {
                 staked_amount: Uint128::new(10_000),
-                user_index: state.global_index,
-                pending_rewards: Uint128::new(10_000)
+                rewards: user_rewards(REWARD_DENOM, Decimal::one(), Uint128::new(10_000)),
             }
         );
 
@@ -209,7 +292,7 @@ pub mod tests {
             )
             .unwrap();
 
-        assert_eq!(user_info.pending_rewards, Uint128::new(15_000));
+        assert_eq!(pending(&user_info, REWARD_DENOM), Uint128::new(15_000));
 
         // query user2 info
         let user_info: UserRewardInfo = app
@@ -222,7 +305,7 @@ pub mod tests {
             )
             .unwrap();
 
-        assert_eq!(user_info.pending_rewards, Uint128::new(5_000));
+        assert_eq!(pending(&user_info, REWARD_DENOM), Uint128::new(5_000));
 
         // user1 claim rewards
         app.execute_contract(
@@ -253,7 +336,7 @@ pub mod tests {
             )
             .unwrap();
 
-        assert_eq!(user_info.pending_rewards, Uint128::zero());
+        assert_eq!(pending(&user_info, REWARD_DENOM), Uint128::zero());
 
         // query user1 reward balance
         let balance = app
@@ -325,7 +408,7 @@ pub mod tests {
                 },
             )
             .unwrap();
-        assert_eq!(user_info.pending_rewards, Uint128::zero());
+        assert_eq!(pending(&user_info, REWARD_DENOM), Uint128::zero());
         assert_eq!(user_info.staked_amount, Uint128::zero());
 
         // user2 gets all reward
@@ -338,7 +421,7 @@ pub mod tests {
                 },
             )
             .unwrap();
-        assert_eq!(user_info.pending_rewards, Uint128::new(15_000));
+        assert_eq!(pending(&user_info, REWARD_DENOM), Uint128::new(15_000));
 
         // user2 perform full withdrawal
         app.execute_contract(
@@ -385,7 +468,7 @@ pub mod tests {
             .unwrap()
             .amount;
 
-        assert_eq!(balance, user_info.pending_rewards);
+        assert_eq!(balance, pending(&user_info, REWARD_DENOM));
 
         // user2 receives funds
         let balance = app
@@ -408,6 +491,1482 @@ pub mod tests {
             .unwrap();
 
         assert_eq!(user_info.staked_amount, Uint128::zero());
-        assert_eq!(user_info.pending_rewards, Uint128::zero());
+        assert_eq!(pending(&user_info, REWARD_DENOM), Uint128::zero());
+    }
+
+    /// USER2 stakes, withdraws everything, misses a reward distributed to
+    /// USER alone and stakes again. Returns the app, the contract and the
+    /// info of USER2 then.
+    fn reenter(contract: Box<dyn Contract<Empty>>) -> (App, Addr, StdResult<UserRewardInfo>) {
+        let (mut app, contract_addr) = instantiate_contract(contract);
+
+        // user2 stakes and leaves
+        app = mint_tokens(app, USER2.to_owned(), Uint128::new(10_000));
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            contract_addr.clone(),
+            &ExecuteMsg::Deposit {},
+            &[coin(10_000, DENOM)],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            contract_addr.clone(),
+            &ExecuteMsg::Withdraw {
+                amount: Uint128::new(10_000),
+            },
+            &[],
+        )
+        .unwrap();
+
+        // owner increases reward, user is the only staker
+        app = mint_reward_tokens(app, OWNER.to_owned(), Uint128::new(10_000));
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            contract_addr.clone(),
+            &ExecuteMsg::IncreaseReward {},
+            &[coin(10_000, REWARD_DENOM)],
+        )
+        .unwrap();
+
+        // user2 comes back
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            contract_addr.clone(),
+            &ExecuteMsg::Deposit {},
+            &[coin(10_000, DENOM)],
+        )
+        .unwrap();
+
+        let user_info = app.wrap().query_wasm_smart(
+            contract_addr.clone(),
+            &QueryMsg::User {
+                user: USER2.to_string(),
+            },
+        );
+        (app, contract_addr, user_info)
+    }
+
+    #[test]
+    fn reentry_rewards_exploit() {
+        let (mut app, contract_addr, user_info) = reenter(challenge_contract());
+
+        // user2 is paid for the time they had nothing staked
+        assert_eq!(
+            pending(&user_info.unwrap(), REWARD_DENOM),
+            Uint128::new(10_000)
+        );
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            contract_addr.clone(),
+            &ExecuteMsg::ClaimRewards {},
+            &[],
+        )
+        .unwrap();
+
+        let balance = app
+            .wrap()
+            .query_balance(USER2.to_string(), REWARD_DENOM)
+            .unwrap()
+            .amount;
+        assert_eq!(balance, Uint128::new(10_000));
+
+        // which leaves the contract short of the rewards owed to user
+        let user_info: UserRewardInfo = app
+            .wrap()
+            .query_wasm_smart(
+                contract_addr.clone(),
+                &QueryMsg::User {
+                    user: USER.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(pending(&user_info, REWARD_DENOM), Uint128::new(20_000));
+        app.execute_contract(
+            Addr::unchecked(USER),
+            contract_addr,
+            &ExecuteMsg::ClaimRewards {},
+            &[],
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn reentry_rewards_fixed() {
+        let (mut app, contract_addr, user_info) = reenter(fixed_contract());
+
+        // user2 gets nothing for the time they had nothing staked
+        assert_eq!(pending(&user_info.unwrap(), REWARD_DENOM), Uint128::zero());
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            contract_addr.clone(),
+            &ExecuteMsg::ClaimRewards {},
+            &[],
+        )
+        .unwrap_err();
+
+        // and user can claim everything distributed while staking alone
+        app.execute_contract(
+            Addr::unchecked(USER),
+            contract_addr,
+            &ExecuteMsg::ClaimRewards {},
+            &[],
+        )
+        .unwrap();
+
+        let balance = app
+            .wrap()
+            .query_balance(USER.to_string(), REWARD_DENOM)
+            .unwrap()
+            .amount;
+        assert_eq!(balance, Uint128::new(20_000));
+    }
+
+    type Instantiate =
+        fn(DepsMut, Env, MessageInfo, InstantiateMsg) -> Result<Response, ContractError>;
+
+    /// The challenge contract, instantiated by `instantiate`
+    fn contract_instantiated_by(instantiate: Instantiate) -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            crate::contract::execute,
+            instantiate,
+            crate::contract::query,
+        );
+        Box::new(contract)
+    }
+
+    /// Saves the layouts of 0.1.0 mid-stake: USER is owed the rewards
+    /// distributed since staking, USER2 was paid for some when staking more
+    fn save_v0_1(storage: &mut dyn Storage) -> StdResult<()> {
+        let state = v0_1::State {
+            owner: Addr::unchecked(OWNER),
+            total_staked: Uint128::new(15_000),
+            global_index: Decimal::one(),
+        };
+        v0_1::STATE.save(storage, &state)?;
+
+        let user = v0_1::UserRewardInfo {
+            staked_amount: Uint128::new(10_000),
+            user_index: Decimal::zero(),
+            pending_rewards: Uint128::zero(),
+        };
+        v0_1::USERS.save(storage, &Addr::unchecked(USER), &user)?;
+        let user2 = v0_1::UserRewardInfo {
+            staked_amount: Uint128::new(5_000),
+            user_index: Decimal::one(),
+            pending_rewards: Uint128::new(300),
+        };
+        v0_1::USERS.save(storage, &Addr::unchecked(USER2), &user2)
+    }
+
+    /// Instantiates with the storage of 0.1.0, which did not record its version
+    fn instantiate_v0_1(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: InstantiateMsg,
+    ) -> Result<Response, ContractError> {
+        save_v0_1(deps.storage)?;
+        Ok(Response::new())
+    }
+
+    /// Instantiates like a later version
+    fn instantiate_later(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> Result<Response, ContractError> {
+        let response = crate::contract::instantiate(deps.branch(), env, info, msg)?;
+        cw2::set_contract_version(deps.storage, CONTRACT_NAME, "99.0.0")?;
+        Ok(response)
+    }
+
+    /// Instantiates like some other contract
+    fn instantiate_other(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> Result<Response, ContractError> {
+        let response = crate::contract::instantiate(deps.branch(), env, info, msg)?;
+        cw2::set_contract_version(deps.storage, "crates.io:cw20-base", CONTRACT_VERSION)?;
+        Ok(response)
+    }
+
+    /// Sets up the base scenario with `old`, adds USER2 who stakes and
+    /// partly withdraws, then migrates to `new`, failing with the root cause
+    fn migrate_mid_stake(
+        old: Box<dyn Contract<Empty>>,
+        new: Box<dyn Contract<Empty>>,
+    ) -> (App, Addr, Result<AppResponse, String>) {
+        let (mut app, contract_addr) = instantiate_contract(old);
+
+        app = mint_tokens(app, USER2.to_owned(), Uint128::new(10_000));
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            contract_addr.clone(),
+            &ExecuteMsg::Deposit {},
+            &[coin(10_000, DENOM)],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER2),
+            contract_addr.clone(),
+            &ExecuteMsg::Withdraw {
+                amount: Uint128::new(5_000),
+            },
+            &[],
+        )
+        .unwrap();
+
+        let new_id = app.store_code(new);
+        let migrated = app
+            .migrate_contract(
+                Addr::unchecked(OWNER),
+                contract_addr.clone(),
+                &MigrateMsg {},
+                new_id,
+            )
+            .map_err(|err| err.root_cause().to_string());
+        (app, contract_addr, migrated)
+    }
+
+    fn query_user(app: &App, contract_addr: &Addr, user: &str) -> UserRewardInfo {
+        app.wrap()
+            .query_wasm_smart(
+                contract_addr,
+                &QueryMsg::User {
+                    user: user.to_string(),
+                },
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn migrate_live_contract() {
+        let (mut app, contract_addr, migrated) =
+            migrate_mid_stake(challenge_contract(), fixed_contract());
+        migrated.unwrap();
+
+        let version = cw2::query_contract_info(&app.wrap(), &contract_addr).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // stakes and rewards are carried over
+        let state: State = app
+            .wrap()
+            .query_wasm_smart(&contract_addr, &QueryMsg::State {})
+            .unwrap();
+        let now = app.block_info().time;
+        assert_eq!(
+            state,
+            State {
+                owner: Some(Addr::unchecked(OWNER)),
+                pending_owner: None,
+                denom: DENOM.to_string(),
+                compounding: false,
+                unbonding_period: 0,
+                rewards: unstreamed(REWARD_DENOM, Decimal::one(), now, now),
+                last_update: now,
+                total_staked: Uint128::new(15_000),
+            }
+        );
+        assert_eq!(
+            query_user(&app, &contract_addr, USER),
+            UserRewardInfo {
+                staked_amount: Uint128::new(10_000),
+                rewards: user_rewards(REWARD_DENOM, Decimal::one(), Uint128::new(10_000)),
+            }
+        );
+        assert_eq!(
+            query_user(&app, &contract_addr, USER2).staked_amount,
+            Uint128::new(5_000)
+        );
+
+        // and keep accruing
+        app = mint_reward_tokens(app, OWNER.to_owned(), Uint128::new(15_000));
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            contract_addr.clone(),
+            &ExecuteMsg::IncreaseReward {},
+            &[coin(15_000, REWARD_DENOM)],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(USER),
+            contract_addr.clone(),
+            &ExecuteMsg::ClaimRewards {},
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            app.wrap()
+                .query_balance(USER.to_string(), REWARD_DENOM)
+                .unwrap()
+                .amount,
+            Uint128::new(20_000)
+        );
+        assert_eq!(
+            pending(&query_user(&app, &contract_addr, USER2), REWARD_DENOM),
+            Uint128::new(5_000)
+        );
+    }
+
+    #[test]
+    fn migrate_unversioned_contract() {
+        let mut app = App::default();
+        let old_id = app.store_code(contract_instantiated_by(instantiate_v0_1));
+        let contract_addr = app
+            .instantiate_contract(
+                old_id,
+                Addr::unchecked(OWNER),
+                &instantiate_msg(),
+                &[],
+                "test",
+                Some(OWNER.to_string()),
+            )
+            .unwrap();
+
+        let new_id = app.store_code(challenge_contract());
+        let migrated = app
+            .migrate_contract(
+                Addr::unchecked(OWNER),
+                contract_addr.clone(),
+                &MigrateMsg {},
+                new_id,
+            )
+            .unwrap();
+        assert!(migrated.has_event(
+            &Event::new("wasm")
+                .add_attribute("from_version", "0.1.0")
+                .add_attribute("to_version", CONTRACT_VERSION)
+        ));
+
+        let version = cw2::query_contract_info(&app.wrap(), &contract_addr).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // the owner is carried over, with no one pending nor any stream
+        let state: State = app
+            .wrap()
+            .query_wasm_smart(&contract_addr, &QueryMsg::State {})
+            .unwrap();
+        assert_eq!(
+            state,
+            State {
+                owner: Some(Addr::unchecked(OWNER)),
+                pending_owner: None,
+                denom: DENOM.to_string(),
+                compounding: false,
+                unbonding_period: 0,
+                rewards: unstreamed(
+                    REWARD_DENOM,
+                    Decimal::one(),
+                    Timestamp::from_seconds(0),
+                    Timestamp::from_seconds(0)
+                ),
+                last_update: app.block_info().time,
+                total_staked: Uint128::new(15_000),
+            }
+        );
+        assert_eq!(
+            pending(&query_user(&app, &contract_addr, USER), REWARD_DENOM),
+            Uint128::new(10_000)
+        );
+        assert_eq!(
+            pending(&query_user(&app, &contract_addr, USER2), REWARD_DENOM),
+            Uint128::new(300)
+        );
+    }
+
+    #[test]
+    fn migrate_refuses_downgrades() {
+        let (app, contract_addr, migrated) = migrate_mid_stake(
+            contract_instantiated_by(instantiate_later),
+            challenge_contract(),
+        );
+
+        assert_eq!(
+            migrated.unwrap_err(),
+            format!("Cannot downgrade from 99.0.0 to {CONTRACT_VERSION}")
+        );
+        let version = cw2::query_contract_info(&app.wrap(), &contract_addr).unwrap();
+        assert_eq!(version.version, "99.0.0");
+    }
+
+    #[test]
+    fn migrate_refuses_other_contracts() {
+        let (_, _, migrated) = migrate_mid_stake(
+            contract_instantiated_by(instantiate_other),
+            challenge_contract(),
+        );
+
+        assert_eq!(
+            migrated.unwrap_err(),
+            "Cannot migrate from contract crates.io:cw20-base"
+        );
+    }
+
+    #[test]
+    fn migrate_from_v0_1() {
+        let mut storage = MockStorage::new();
+        save_v0_1(&mut storage).unwrap();
+
+        convert(&mut storage, &Version::new(0, 1, 0)).unwrap();
+
+        // the hard-coded denoms
+        assert_eq!(
+            STATE.load(&storage).unwrap(),
+            State {
+                owner: Some(Addr::unchecked(OWNER)),
+                pending_owner: None,
+                denom: v0_1::DENOM.to_string(),
+                compounding: false,
+                unbonding_period: 0,
+                rewards: unstreamed(
+                    v0_1::REWARD_DENOM,
+                    Decimal::one(),
+                    Timestamp::from_seconds(0),
+                    Timestamp::from_seconds(0)
+                ),
+                last_update: Timestamp::from_seconds(0),
+                total_staked: Uint128::new(15_000),
+            }
+        );
+        assert_eq!(
+            USERS.load(&storage, &Addr::unchecked(USER)).unwrap(),
+            UserRewardInfo {
+                staked_amount: Uint128::new(10_000),
+                rewards: user_rewards(v0_1::REWARD_DENOM, Decimal::zero(), Uint128::zero()),
+            }
+        );
+        assert_eq!(
+            USERS.load(&storage, &Addr::unchecked(USER2)).unwrap(),
+            UserRewardInfo {
+                staked_amount: Uint128::new(5_000),
+                rewards: user_rewards(v0_1::REWARD_DENOM, Decimal::one(), Uint128::new(300)),
+            }
+        );
+    }
+
+    /// Executes `msg` as `sender`, failing with the root cause
+    fn execute_as(
+        app: &mut App,
+        contract_addr: &Addr,
+        sender: &str,
+        msg: ExecuteMsg,
+        funds: &[Coin],
+    ) -> Result<AppResponse, String> {
+        app.execute_contract(Addr::unchecked(sender), contract_addr.clone(), &msg, funds)
+            .map_err(|err| err.root_cause().to_string())
+    }
+
+    fn query_ownership(app: &App, contract_addr: &Addr) -> OwnershipResponse {
+        app.wrap()
+            .query_wasm_smart(contract_addr, &QueryMsg::Ownership {})
+            .unwrap()
+    }
+
+    const NEW_OWNER: &str = "new_owner";
+
+    #[test]
+    fn ownership_is_transferred_in_two_steps() {
+        let (mut app, contract_addr) = proper_instantiate();
+        app = mint_reward_tokens(app, NEW_OWNER.to_owned(), Uint128::new(10_000));
+        let propose = || ExecuteMsg::ProposeOwner {
+            owner: NEW_OWNER.to_string(),
+        };
+
+        // only the owner proposes
+        assert_eq!(
+            execute_as(&mut app, &contract_addr, USER, propose(), &[]).unwrap_err(),
+            "Unauthorized"
+        );
+        execute_as(&mut app, &contract_addr, OWNER, propose(), &[]).unwrap();
+        assert_eq!(
+            query_ownership(&app, &contract_addr),
+            OwnershipResponse {
+                owner: Some(Addr::unchecked(OWNER)),
+                pending_owner: Some(Addr::unchecked(NEW_OWNER)),
+            }
+        );
+
+        // until accepted, the proposed owner has no say
+        let increase_reward = || ExecuteMsg::IncreaseReward {};
+        assert_eq!(
+            execute_as(
+                &mut app,
+                &contract_addr,
+                NEW_OWNER,
+                increase_reward(),
+                &[coin(1_000, REWARD_DENOM)]
+            )
+            .unwrap_err(),
+            "Unauthorized"
+        );
+
+        // only the proposed owner accepts
+        assert_eq!(
+            execute_as(
+                &mut app,
+                &contract_addr,
+                USER,
+                ExecuteMsg::AcceptOwnership {},
+                &[]
+            )
+            .unwrap_err(),
+            "Unauthorized"
+        );
+        execute_as(
+            &mut app,
+            &contract_addr,
+            NEW_OWNER,
+            ExecuteMsg::AcceptOwnership {},
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            query_ownership(&app, &contract_addr),
+            OwnershipResponse {
+                owner: Some(Addr::unchecked(NEW_OWNER)),
+                pending_owner: None,
+            }
+        );
+
+        // the new owner takes over the rewards
+        execute_as(
+            &mut app,
+            &contract_addr,
+            NEW_OWNER,
+            increase_reward(),
+            &[coin(1_000, REWARD_DENOM)],
+        )
+        .unwrap();
+        app = mint_reward_tokens(app, OWNER.to_owned(), Uint128::new(1_000));
+        assert_eq!(
+            execute_as(
+                &mut app,
+                &contract_addr,
+                OWNER,
+                increase_reward(),
+                &[coin(1_000, REWARD_DENOM)]
+            )
+            .unwrap_err(),
+            "Unauthorized"
+        );
+        assert_eq!(
+            execute_as(&mut app, &contract_addr, OWNER, propose(), &[]).unwrap_err(),
+            "Unauthorized"
+        );
+    }
+
+    #[test]
+    fn ownership_cannot_be_accepted_without_proposal() {
+        let (mut app, contract_addr) = proper_instantiate();
+
+        assert_eq!(
+            execute_as(
+                &mut app,
+                &contract_addr,
+                NEW_OWNER,
+                ExecuteMsg::AcceptOwnership {},
+                &[]
+            )
+            .unwrap_err(),
+            "No owner was proposed"
+        );
+    }
+
+    #[test]
+    fn ownership_can_be_renounced() {
+        let (mut app, contract_addr) = proper_instantiate();
+        execute_as(
+            &mut app,
+            &contract_addr,
+            OWNER,
+            ExecuteMsg::ProposeOwner {
+                owner: NEW_OWNER.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+        // only the owner renounces
+        assert_eq!(
+            execute_as(
+                &mut app,
+                &contract_addr,
+                USER,
+                ExecuteMsg::RenounceOwnership {},
+                &[]
+            )
+            .unwrap_err(),
+            "Unauthorized"
+        );
+        execute_as(
+            &mut app,
+            &contract_addr,
+            OWNER,
+            ExecuteMsg::RenounceOwnership {},
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            query_ownership(&app, &contract_addr),
+            OwnershipResponse {
+                owner: None,
+                pending_owner: None,
+            }
+        );
+
+        // which withdraws the proposal, and no one increases rewards anymore
+        assert_eq!(
+            execute_as(
+                &mut app,
+                &contract_addr,
+                NEW_OWNER,
+                ExecuteMsg::AcceptOwnership {},
+                &[]
+            )
+            .unwrap_err(),
+            "No owner was proposed"
+        );
+        app = mint_reward_tokens(app, OWNER.to_owned(), Uint128::new(1_000));
+        assert_eq!(
+            execute_as(
+                &mut app,
+                &contract_addr,
+                OWNER,
+                ExecuteMsg::IncreaseReward {},
+                &[coin(1_000, REWARD_DENOM)]
+            )
+            .unwrap_err(),
+            "Unauthorized"
+        );
+    }
+
+    /// Instantiates a pool of `msg` with the challenge code
+    fn instantiate_pool(app: &mut App, msg: &InstantiateMsg) -> Result<Addr, String> {
+        let code_id = app.store_code(challenge_contract());
+        app.instantiate_contract(code_id, Addr::unchecked(OWNER), msg, &[], "pool", None)
+            .map_err(|err| err.root_cause().to_string())
+    }
+
+    fn mint(app: &mut App, recipient: &str, amount: Coin) {
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: recipient.to_owned(),
+            amount: vec![amount],
+        }))
+        .unwrap();
+    }
+
+    #[test]
+    fn pools_use_their_denoms() {
+        let mut app = App::default();
+        let msg = InstantiateMsg {
+            denom: "ustake".to_string(),
+            reward_denoms: vec!["ureward".to_string()],
+            compounding: false,
+            unbonding_period: 0,
+        };
+        let contract_addr = instantiate_pool(&mut app, &msg).unwrap();
+
+        let config: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(&contract_addr, &QueryMsg::Config {})
+            .unwrap();
+        assert_eq!(
+            config,
+            ConfigResponse {
+                denom: "ustake".to_string(),
+                reward_denoms: vec!["ureward".to_string()],
+                compounding: false,
+                unbonding_period: 0,
+            }
+        );
+
+        // the denoms of the challenge are not taken
+        mint(&mut app, USER, coin(10_000, DENOM));
+        assert_eq!(
+            execute_as(
+                &mut app,
+                &contract_addr,
+                USER,
+                ExecuteMsg::Deposit {},
+                &[coin(10_000, DENOM)]
+            )
+            .unwrap_err(),
+            "Caller did not provide requested funds"
+        );
+
+        mint(&mut app, USER, coin(10_000, "ustake"));
+        mint(&mut app, OWNER, coin(10_000, "ureward"));
+        execute_as(
+            &mut app,
+            &contract_addr,
+            USER,
+            ExecuteMsg::Deposit {},
+            &[coin(10_000, "ustake")],
+        )
+        .unwrap();
+        execute_as(
+            &mut app,
+            &contract_addr,
+            OWNER,
+            ExecuteMsg::IncreaseReward {},
+            &[coin(10_000, "ureward")],
+        )
+        .unwrap();
+        execute_as(
+            &mut app,
+            &contract_addr,
+            USER,
+            ExecuteMsg::ClaimRewards {},
+            &[],
+        )
+        .unwrap();
+        execute_as(
+            &mut app,
+            &contract_addr,
+            USER,
+            ExecuteMsg::Withdraw {
+                amount: Uint128::new(10_000),
+            },
+            &[],
+        )
+        .unwrap();
+
+        let balances = app.wrap().query_all_balances(USER).unwrap();
+        assert_eq!(
+            balances,
+            vec![
+                coin(10_000, DENOM),
+                coin(10_000, "ureward"),
+                coin(10_000, "ustake")
+            ]
+        );
+    }
+
+    #[test]
+    fn pools_validate_their_denoms() {
+        let mut app = App::default();
+
+        let msg = InstantiateMsg {
+            denom: DENOM.to_string(),
+            reward_denoms: vec![DENOM.to_string()],
+            compounding: false,
+            unbonding_period: 0,
+        };
+        assert_eq!(
+            instantiate_pool(&mut app, &msg).unwrap_err(),
+            "Stake and reward denoms should differ"
+        );
+
+        let msg = InstantiateMsg {
+            compounding: true,
+            ..instantiate_msg()
+        };
+        assert_eq!(
+            instantiate_pool(&mut app, &msg).unwrap_err(),
+            "Compounding needs rewards in the staked denom"
+        );
+    }
+
+    #[test]
+    fn compounding_pools_stake_claimed_rewards() {
+        let mut app = App::default();
+        let msg = InstantiateMsg {
+            denom: DENOM.to_string(),
+            reward_denoms: vec![DENOM.to_string()],
+            compounding: true,
+            unbonding_period: 0,
+        };
+        let contract_addr = instantiate_pool(&mut app, &msg).unwrap();
+
+        mint(&mut app, USER, coin(10_000, DENOM));
+        mint(&mut app, OWNER, coin(5_000, DENOM));
+        execute_as(
+            &mut app,
+            &contract_addr,
+            USER,
+            ExecuteMsg::Deposit {},
+            &[coin(10_000, DENOM)],
+        )
+        .unwrap();
+        execute_as(
+            &mut app,
+            &contract_addr,
+            OWNER,
+            ExecuteMsg::IncreaseReward {},
+            &[coin(5_000, DENOM)],
+        )
+        .unwrap();
+
+        // the rewards are staked rather than paid out
+        execute_as(
+            &mut app,
+            &contract_addr,
+            USER,
+            ExecuteMsg::ClaimRewards {},
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            query_user(&app, &contract_addr, USER),
+            UserRewardInfo {
+                staked_amount: Uint128::new(15_000),
+                rewards: user_rewards(DENOM, Decimal::from_ratio(1u128, 2u128), Uint128::zero()),
+            }
+        );
+        let state: State = app
+            .wrap()
+            .query_wasm_smart(&contract_addr, &QueryMsg::State {})
+            .unwrap();
+        assert_eq!(state.total_staked, Uint128::new(15_000));
+        assert_eq!(
+            app.wrap().query_balance(USER, DENOM).unwrap().amount,
+            Uint128::zero()
+        );
+
+        // and withdrawn along with the stake
+        execute_as(
+            &mut app,
+            &contract_addr,
+            USER,
+            ExecuteMsg::Withdraw {
+                amount: Uint128::new(15_000),
+            },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            app.wrap().query_balance(USER, DENOM).unwrap().amount,
+            Uint128::new(15_000)
+        );
+    }
+
+    fn advance(app: &mut App, seconds: u64) {
+        app.update_block(|block| block.time = block.time.plus_seconds(seconds));
+    }
+
+    fn query_state(app: &App, contract_addr: &Addr) -> State {
+        app.wrap()
+            .query_wasm_smart(contract_addr, &QueryMsg::State {})
+            .unwrap()
+    }
+
+    /// Streams `rate` rewards per second from `start` to `end` seconds ahead
+    fn stream(rate: u128, start: u64, end: u64, app: &App) -> ExecuteMsg {
+        let now = app.block_info().time;
+        ExecuteMsg::StreamReward {
+            rate: Uint128::new(rate),
+            start: now.plus_seconds(start),
+            end: now.plus_seconds(end),
+        }
+    }
+
+    #[test]
+    fn rewards_are_streamed_over_time() {
+        let (mut app, contract_addr) = proper_instantiate();
+        app = mint_reward_tokens(app, OWNER.to_owned(), Uint128::new(1_000));
+        app = mint_tokens(app, USER2.to_owned(), Uint128::new(10_000));
+
+        let msg = stream(10, 10, 110, &app);
+        execute_as(
+            &mut app,
+            &contract_addr,
+            OWNER,
+            msg,
+            &[coin(1_000, REWARD_DENOM)],
+        )
+        .unwrap();
+
+        // nothing before the start
+        advance(&mut app, 10);
+        assert_eq!(
+            pending(&query_user(&app, &contract_addr, USER), REWARD_DENOM),
+            Uint128::new(10_000)
+        );
+
+        // then shared by the stakers of every second
+        advance(&mut app, 50);
+        assert_eq!(
+            pending(&query_user(&app, &contract_addr, USER), REWARD_DENOM),
+            Uint128::new(10_500)
+        );
+        execute_as(
+            &mut app,
+            &contract_addr,
+            USER2,
+            ExecuteMsg::Deposit {},
+            &[coin(10_000, DENOM)],
+        )
+        .unwrap();
+        advance(&mut app, 50);
+        assert_eq!(
+            pending(&query_user(&app, &contract_addr, USER), REWARD_DENOM),
+            Uint128::new(10_750)
+        );
+        assert_eq!(
+            pending(&query_user(&app, &contract_addr, USER2), REWARD_DENOM),
+            Uint128::new(250)
+        );
+
+        // and nothing after the end
+        advance(&mut app, 100);
+        execute_as(
+            &mut app,
+            &contract_addr,
+            USER,
+            ExecuteMsg::ClaimRewards {},
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            app.wrap().query_balance(USER, REWARD_DENOM).unwrap().amount,
+            Uint128::new(10_750)
+        );
+        assert_eq!(
+            pending(&query_user(&app, &contract_addr, USER2), REWARD_DENOM),
+            Uint128::new(250)
+        );
+        assert_eq!(
+            query_state(&app, &contract_addr).rewards[REWARD_DENOM].global_index,
+            Decimal::from_ratio(1_075u128, 1_000u128)
+        );
+    }
+
+    #[test]
+    fn rewards_are_carried_while_nobody_staked() {
+        let mut app = App::default();
+        let contract_addr = instantiate_pool(&mut app, &instantiate_msg()).unwrap();
+        mint(&mut app, OWNER, coin(2_000, REWARD_DENOM));
+        mint(&mut app, USER, coin(10_000, DENOM));
+
+        execute_as(
+            &mut app,
+            &contract_addr,
+            OWNER,
+            ExecuteMsg::IncreaseReward {},
+            &[coin(1_000, REWARD_DENOM)],
+        )
+        .unwrap();
+        let msg = stream(10, 0, 100, &app);
+        execute_as(
+            &mut app,
+            &contract_addr,
+            OWNER,
+            msg,
+            &[coin(1_000, REWARD_DENOM)],
+        )
+        .unwrap();
+        advance(&mut app, 50);
+
+        let state = query_state(&app, &contract_addr);
+        let reward = &state.rewards[REWARD_DENOM];
+        assert_eq!(reward.unallocated, Uint128::new(1_500));
+        assert_eq!(reward.global_index, Decimal::zero());
+
+        // the first staker takes them all
+        execute_as(
+            &mut app,
+            &contract_addr,
+            USER,
+            ExecuteMsg::Deposit {},
+            &[coin(10_000, DENOM)],
+        )
+        .unwrap();
+        let state = query_state(&app, &contract_addr);
+        let reward = &state.rewards[REWARD_DENOM];
+        assert_eq!(reward.unallocated, Uint128::zero());
+        assert_eq!(reward.global_index, Decimal::from_ratio(15u128, 100u128));
+
+        advance(&mut app, 50);
+        execute_as(
+            &mut app,
+            &contract_addr,
+            USER,
+            ExecuteMsg::ClaimRewards {},
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            app.wrap().query_balance(USER, REWARD_DENOM).unwrap().amount,
+            Uint128::new(2_000)
+        );
+    }
+
+    #[test]
+    fn streams_are_validated() {
+        let (mut app, contract_addr) = proper_instantiate();
+        app = mint_reward_tokens(app, OWNER.to_owned(), Uint128::new(2_000));
+        app = mint_reward_tokens(app, USER.to_owned(), Uint128::new(1_000));
+        let funds = [coin(1_000, REWARD_DENOM)];
+
+        let msg = stream(10, 0, 100, &app);
+        assert_eq!(
+            execute_as(&mut app, &contract_addr, USER, msg, &funds).unwrap_err(),
+            "Unauthorized"
+        );
+
+        advance(&mut app, 10);
+        let mut msg = stream(10, 0, 100, &app);
+        if let ExecuteMsg::StreamReward { start, .. } = &mut msg {
+            *start = start.minus_seconds(1);
+        }
+        assert_eq!(
+            execute_as(&mut app, &contract_addr, OWNER, msg, &funds).unwrap_err(),
+            "Reward stream should end after it starts, which is not in the past"
+        );
+        let msg = stream(10, 100, 100, &app);
+        assert_eq!(
+            execute_as(&mut app, &contract_addr, OWNER, msg, &[]).unwrap_err(),
+            "Caller did not provide requested funds"
+        );
+        let msg = stream(10, 100, 100, &app);
+        assert_eq!(
+            execute_as(&mut app, &contract_addr, OWNER, msg, &funds).unwrap_err(),
+            "Reward stream should end after it starts, which is not in the past"
+        );
+        let msg = stream(10, 0, 50, &app);
+        assert_eq!(
+            execute_as(&mut app, &contract_addr, OWNER, msg, &funds).unwrap_err(),
+            "Reward stream needs 500 to be sent along"
+        );
+        let msg = stream(u128::MAX, 0, 2, &app);
+        assert_eq!(
+            execute_as(&mut app, &contract_addr, OWNER, msg, &funds).unwrap_err(),
+            "Reward stream pays out more than can be held"
+        );
+
+        let msg = stream(10, 0, 100, &app);
+        execute_as(&mut app, &contract_addr, OWNER, msg, &funds).unwrap();
+        advance(&mut app, 99);
+        let msg = stream(10, 0, 100, &app);
+        assert_eq!(
+            execute_as(&mut app, &contract_addr, OWNER, msg, &funds).unwrap_err(),
+            "A reward stream is still running"
+        );
+        advance(&mut app, 1);
+        let msg = stream(10, 0, 100, &app);
+        execute_as(&mut app, &contract_addr, OWNER, msg, &funds).unwrap();
+    }
+
+    fn query_unbondings(app: &App, contract_addr: &Addr, user: &str) -> Vec<Unbonding> {
+        let response: UnbondingsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_addr,
+                &QueryMsg::Unbondings {
+                    user: user.to_string(),
+                },
+            )
+            .unwrap();
+        response.unbondings
+    }
+
+    fn withdraw(app: &mut App, contract_addr: &Addr, user: &str, amount: u128) {
+        let msg = ExecuteMsg::Withdraw {
+            amount: Uint128::new(amount),
+        };
+        execute_as(app, contract_addr, user, msg, &[]).unwrap();
+    }
+
+    #[test]
+    fn withdrawals_unbond_before_release() {
+        let mut app = App::default();
+        let msg = InstantiateMsg {
+            unbonding_period: 100,
+            ..instantiate_msg()
+        };
+        let contract_addr = instantiate_pool(&mut app, &msg).unwrap();
+        let start = app.block_info().time;
+        mint(&mut app, USER, coin(10_000, DENOM));
+        mint(&mut app, USER2, coin(5_000, DENOM));
+        mint(&mut app, OWNER, coin(10_000, REWARD_DENOM));
+        for (user, amount) in [(USER, 10_000), (USER2, 5_000)] {
+            execute_as(
+                &mut app,
+                &contract_addr,
+                user,
+                ExecuteMsg::Deposit {},
+                &[coin(amount, DENOM)],
+            )
+            .unwrap();
+        }
+
+        // overlapping withdrawals, each with its own release
+        withdraw(&mut app, &contract_addr, USER, 3_000);
+        advance(&mut app, 40);
+        withdraw(&mut app, &contract_addr, USER, 2_000);
+        assert_eq!(
+            query_unbondings(&app, &contract_addr, USER),
+            vec![
+                Unbonding {
+                    amount: Uint128::new(3_000),
+                    release_at: start.plus_seconds(100),
+                },
+                Unbonding {
+                    amount: Uint128::new(2_000),
+                    release_at: start.plus_seconds(140),
+                },
+            ]
+        );
+        assert_eq!(query_unbondings(&app, &contract_addr, USER2), vec![]);
+        assert_eq!(
+            app.wrap().query_balance(USER, DENOM).unwrap().amount,
+            Uint128::zero()
+        );
+
+        // unbonding funds earn nothing
+        assert_eq!(
+            query_state(&app, &contract_addr).total_staked,
+            Uint128::new(10_000)
+        );
+        execute_as(
+            &mut app,
+            &contract_addr,
+            OWNER,
+            ExecuteMsg::IncreaseReward {},
+            &[coin(10_000, REWARD_DENOM)],
+        )
+        .unwrap();
+        for user in [USER, USER2] {
+            assert_eq!(
+                pending(&query_user(&app, &contract_addr, user), REWARD_DENOM),
+                Uint128::new(5_000)
+            );
+        }
+
+        let claim = ExecuteMsg::ClaimUnbonded {};
+        assert_eq!(
+            execute_as(&mut app, &contract_addr, USER, claim.clone(), &[]).unwrap_err(),
+            "No unbonded funds to claim"
+        );
+
+        // only released entries are paid out
+        advance(&mut app, 60);
+        execute_as(&mut app, &contract_addr, USER, claim.clone(), &[]).unwrap();
+        assert_eq!(
+            app.wrap().query_balance(USER, DENOM).unwrap().amount,
+            Uint128::new(3_000)
+        );
+        assert_eq!(
+            query_unbondings(&app, &contract_addr, USER),
+            vec![Unbonding {
+                amount: Uint128::new(2_000),
+                release_at: start.plus_seconds(140),
+            }]
+        );
+
+        // and together once several are
+        withdraw(&mut app, &contract_addr, USER, 1_000);
+        advance(&mut app, 100);
+        execute_as(&mut app, &contract_addr, USER, claim.clone(), &[]).unwrap();
+        assert_eq!(
+            app.wrap().query_balance(USER, DENOM).unwrap().amount,
+            Uint128::new(6_000)
+        );
+        assert_eq!(query_unbondings(&app, &contract_addr, USER), vec![]);
+        assert_eq!(
+            execute_as(&mut app, &contract_addr, USER, claim, &[]).unwrap_err(),
+            "No unbonded funds to claim"
+        );
+
+        // the rest is still staked
+        assert_eq!(
+            query_user(&app, &contract_addr, USER).staked_amount,
+            Uint128::new(4_000)
+        );
+        assert_eq!(
+            app.wrap()
+                .query_balance(&contract_addr, DENOM)
+                .unwrap()
+                .amount,
+            Uint128::new(9_000)
+        );
+    }
+
+    fn query_users(
+        app: &App,
+        contract_addr: &Addr,
+        start_after: Option<&str>,
+        limit: Option<u32>,
+    ) -> Vec<UserEntry> {
+        let response: UsersResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_addr,
+                &QueryMsg::Users {
+                    start_after: start_after.map(str::to_string),
+                    limit,
+                },
+            )
+            .unwrap();
+        response.users
+    }
+
+    #[test]
+    fn users_are_paginated() {
+        let (mut app, contract_addr) = proper_instantiate();
+        for user in [USER2, "user3"] {
+            mint(&mut app, user, coin(5_000, DENOM));
+            execute_as(
+                &mut app,
+                &contract_addr,
+                user,
+                ExecuteMsg::Deposit {},
+                &[coin(5_000, DENOM)],
+            )
+            .unwrap();
+        }
+
+        let first = query_users(&app, &contract_addr, None, Some(2));
+        assert_eq!(
+            first,
+            vec![
+                UserEntry {
+                    address: Addr::unchecked(USER),
+                    info: query_user(&app, &contract_addr, USER),
+                },
+                UserEntry {
+                    address: Addr::unchecked(USER2),
+                    info: query_user(&app, &contract_addr, USER2),
+                },
+            ]
+        );
+        // rewards are brought up to date
+        assert_eq!(pending(&first[0].info, REWARD_DENOM), Uint128::new(10_000));
+
+        let rest = query_users(&app, &contract_addr, Some(USER2), Some(2));
+        assert_eq!(
+            rest.iter()
+                .map(|user| user.address.as_str())
+                .collect::<Vec<_>>(),
+            vec!["user3"]
+        );
+        assert_eq!(
+            query_users(&app, &contract_addr, Some("user3"), None),
+            vec![]
+        );
+        assert_eq!(query_users(&app, &contract_addr, None, None).len(), 3);
+    }
+
+    #[test]
+    fn claims_are_simulated() {
+        let (mut app, contract_addr) = proper_instantiate();
+        app = mint_tokens(app, USER2.to_owned(), Uint128::new(10_000));
+        app = mint_reward_tokens(app, OWNER.to_owned(), Uint128::new(10_000));
+        execute_as(
+            &mut app,
+            &contract_addr,
+            USER2,
+            ExecuteMsg::Deposit {},
+            &[coin(10_000, DENOM)],
+        )
+        .unwrap();
+        execute_as(
+            &mut app,
+            &contract_addr,
+            OWNER,
+            ExecuteMsg::IncreaseReward {},
+            &[coin(10_000, REWARD_DENOM)],
+        )
+        .unwrap();
+
+        let simulate = |app: &App, user: &str| {
+            let response: SimulateClaimResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    &contract_addr,
+                    &QueryMsg::SimulateClaim {
+                        user: user.to_string(),
+                    },
+                )
+                .unwrap();
+            response.rewards
+        };
+        let total_pending = |app: &App| {
+            let response: TotalPendingRewardsResponse = app
+                .wrap()
+                .query_wasm_smart(&contract_addr, &QueryMsg::TotalPendingRewards {})
+                .unwrap();
+            response.rewards
+        };
+
+        assert_eq!(simulate(&app, USER), vec![coin(15_000, REWARD_DENOM)]);
+        assert_eq!(simulate(&app, USER2), vec![coin(5_000, REWARD_DENOM)]);
+        assert_eq!(simulate(&app, "nobody"), vec![coin(0, REWARD_DENOM)]);
+        assert_eq!(total_pending(&app), vec![coin(20_000, REWARD_DENOM)]);
+
+        // the claim pays what was simulated
+        execute_as(
+            &mut app,
+            &contract_addr,
+            USER,
+            ExecuteMsg::ClaimRewards {},
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            app.wrap().query_balance(USER, REWARD_DENOM).unwrap().amount,
+            Uint128::new(15_000)
+        );
+        assert_eq!(simulate(&app, USER), vec![coin(0, REWARD_DENOM)]);
+        assert_eq!(total_pending(&app), vec![coin(5_000, REWARD_DENOM)]);
+    }
+
+    #[test]
+    fn unknown_users_start_at_the_current_index() {
+        for contract in [challenge_contract(), fixed_contract()] {
+            let (app, contract_addr) = instantiate_contract(contract);
+
+            // like a first deposit would, with nothing pending
+            assert_eq!(
+                query_user(&app, &contract_addr, "nobody"),
+                UserRewardInfo {
+                    staked_amount: Uint128::zero(),
+                    rewards: user_rewards(REWARD_DENOM, Decimal::one(), Uint128::zero()),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn user_queries_validate_addresses() {
+        let (app, contract_addr) = proper_instantiate();
+
+        // invalid addresses fail rather than panic
+        for msg in [
+            QueryMsg::User {
+                user: "USER".to_string(),
+            },
+            QueryMsg::SimulateClaim {
+                user: "USER".to_string(),
+            },
+            QueryMsg::Users {
+                start_after: Some("USER".to_string()),
+                limit: None,
+            },
+        ] {
+            app.wrap()
+                .query_wasm_smart::<Empty>(&contract_addr, &msg)
+                .unwrap_err();
+        }
+    }
+
+    /// A pool paying rewards in both `REWARD_DENOM` and `BONUS_DENOM`
+    fn two_reward_pool(app: &mut App) -> Addr {
+        let msg = InstantiateMsg {
+            reward_denoms: vec![REWARD_DENOM.to_string(), BONUS_DENOM.to_string()],
+            ..instantiate_msg()
+        };
+        instantiate_pool(app, &msg).unwrap()
+    }
+
+    #[test]
+    fn rewards_are_paid_in_every_denom() {
+        let mut app = App::default();
+        let contract_addr = two_reward_pool(&mut app);
+        mint(&mut app, USER, coin(10_000, DENOM));
+        mint(&mut app, USER2, coin(30_000, DENOM));
+        mint(&mut app, OWNER, coin(1_000, REWARD_DENOM));
+        mint(&mut app, OWNER, coin(2_000, BONUS_DENOM));
+        for (user, amount) in [(USER, 10_000), (USER2, 30_000)] {
+            execute_as(
+                &mut app,
+                &contract_addr,
+                user,
+                ExecuteMsg::Deposit {},
+                &[coin(amount, DENOM)],
+            )
+            .unwrap();
+        }
+        for funds in [coin(1_000, REWARD_DENOM), coin(2_000, BONUS_DENOM)] {
+            execute_as(
+                &mut app,
+                &contract_addr,
+                OWNER,
+                ExecuteMsg::IncreaseReward {},
+                &[funds],
+            )
+            .unwrap();
+        }
+
+        let config: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(&contract_addr, &QueryMsg::Config {})
+            .unwrap();
+        assert_eq!(config.reward_denoms, vec![BONUS_DENOM, REWARD_DENOM]);
+        assert_eq!(
+            query_user(&app, &contract_addr, USER2).rewards.len(),
+            2,
+            "every user earns in every denom"
+        );
+
+        // a single send pays both denoms
+        let response = execute_as(
+            &mut app,
+            &contract_addr,
+            USER,
+            ExecuteMsg::ClaimRewards {},
+            &[],
+        )
+        .unwrap();
+        let transfers: Vec<&Event> = response
+            .events
+            .iter()
+            .filter(|event| event.ty == "transfer")
+            .collect();
+        assert_eq!(transfers.len(), 1);
+        assert!(transfers[0]
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "amount" && attr.value == "500ubonus,250uoak"));
+        assert_eq!(
+            app.wrap().query_all_balances(USER).unwrap(),
+            vec![coin(500, BONUS_DENOM), coin(250, REWARD_DENOM)]
+        );
+
+        let user = query_user(&app, &contract_addr, USER2);
+        assert_eq!(pending(&user, REWARD_DENOM), Uint128::new(750));
+        assert_eq!(pending(&user, BONUS_DENOM), Uint128::new(1_500));
+    }
+
+    #[test]
+    fn reward_denoms_are_validated() {
+        let mut app = App::default();
+        let msg = InstantiateMsg {
+            reward_denoms: vec![],
+            ..instantiate_msg()
+        };
+        assert_eq!(
+            instantiate_pool(&mut app, &msg).unwrap_err(),
+            "At least one reward denom is needed"
+        );
+
+        let contract_addr = two_reward_pool(&mut app);
+        mint(&mut app, USER, coin(10_000, DENOM));
+        mint(&mut app, OWNER, coin(1_000, "uother"));
+        execute_as(
+            &mut app,
+            &contract_addr,
+            USER,
+            ExecuteMsg::Deposit {},
+            &[coin(10_000, DENOM)],
+        )
+        .unwrap();
+
+        let funds = [coin(1_000, "uother")];
+        let msg = ExecuteMsg::IncreaseReward {};
+        assert_eq!(
+            execute_as(&mut app, &contract_addr, OWNER, msg, &funds).unwrap_err(),
+            "uother is not a reward denom"
+        );
+        let msg = stream(10, 0, 100, &app);
+        assert_eq!(
+            execute_as(&mut app, &contract_addr, OWNER, msg, &funds).unwrap_err(),
+            "uother is not a reward denom"
+        );
     }
 }
@@ -1,7 +1,11 @@
 pub mod contract;
 mod error;
+pub mod fixed;
 pub mod integration_tests;
+pub mod mbt_tests;
+pub mod migration;
 pub mod msg;
+pub mod prop_tests;
 pub mod state;
 
 pub use crate::error::ContractError;
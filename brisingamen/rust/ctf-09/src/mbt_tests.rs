@@ -0,0 +1,431 @@
+/// Model-based tests: replays ITF traces of `brisingamenTest.qnt` through
+/// `cw-multi-test`, checking the contract against the spec after every step.
+///
+/// Traces are read from the `traces` directory of the crate, which holds a
+/// fixture, more being added with e.g.
+/// `quint run --mbt --out-itf=rust/ctf-09/traces/out.itf.json brisingamenTest.qnt`.
+/// Traces of `brisingamenTwinTest.qnt` in `traces/twin`, e.g. from
+/// `quint run --mbt --init=initGenesis --out-itf=rust/ctf-09/traces/twin/out.itf.json brisingamenTwinTest.qnt`,
+/// replay the original spec against `contract` and its twin against `fixed`.
+///
+/// The spec has neither reward streams nor block time, so `Replay::check`
+/// hard-codes `reward_rate: 0` and `reward_start = reward_end = now`: the
+/// traces never exercise `stream_reward`, nor `accrue` over time, which are
+/// left to the integration tests.
+#[cfg(test)]
+pub mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::Path;
+
+    use crate::{
+        integration_tests::tests::{
+            challenge_contract, fixed_contract, instantiate_msg, mint_tokens, BONUS_DENOM, DENOM,
+            REWARD_DENOM,
+        },
+        msg::{ExecuteMsg, InstantiateMsg},
+        state::{Reward, State, UserReward, UserRewardInfo, STATE, USERS},
+    };
+    use cosmwasm_std::{
+        coin, testing::MockStorage, Addr, Coin, Decimal, Empty, Order, Storage, Uint128,
+    };
+    use cw_multi_test::{App, BankSudo, Contract, Executor, SudoMsg};
+    use itf::de::{self, As, Integer, Same};
+    use num_bigint::BigInt;
+    use serde::Deserialize;
+
+    /// `CONTRACT` in the spec
+    const SPEC_CONTRACT: &str = "contract";
+
+    /// `REWARD_DENOMS` in the spec
+    const SPEC_REWARD_DENOMS: [&str; 2] = [REWARD_DENOM, BONUS_DENOM];
+
+    /// `State` of the spec, decimals being given by their atomics
+    #[derive(Debug, Deserialize)]
+    struct SpecState {
+        owner: String,
+        #[serde(with = "As::<Integer>")]
+        total_staked: u128,
+        #[serde(with = "As::<BTreeMap<Same, Integer>>")]
+        global_index: BTreeMap<String, u128>,
+        #[serde(with = "As::<BTreeMap<Same, Integer>>")]
+        unallocated: BTreeMap<String, u128>,
+    }
+
+    /// `UserReward` of the spec
+    #[derive(Debug, Deserialize)]
+    struct SpecUserReward {
+        #[serde(with = "As::<Integer>")]
+        user_index: u128,
+        #[serde(with = "As::<Integer>")]
+        pending_rewards: u128,
+    }
+
+    /// `UserRewardInfo` of the spec
+    #[derive(Debug, Deserialize)]
+    struct SpecUser {
+        #[serde(with = "As::<Integer>")]
+        staked_amount: u128,
+        rewards: BTreeMap<String, SpecUserReward>,
+    }
+
+    #[derive(Clone, Debug, Deserialize)]
+    struct NondetPicks {
+        #[serde(default, with = "As::<de::Option::<_>>")]
+        sender: Option<String>,
+        #[serde(default, with = "As::<de::Option::<_>>")]
+        denom: Option<String>,
+        #[serde(default, with = "As::<de::Option::<BigInt>>")]
+        amount: Option<BigInt>,
+    }
+
+    /// The variables of `brisingamenTest`
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Vars {
+        #[serde(with = "As::<BTreeMap<Same, Integer>>")]
+        balances: BTreeMap<String, u128>,
+        #[serde(with = "As::<BTreeMap<Same, BTreeMap<Same, Integer>>>")]
+        balances_rewards: BTreeMap<String, BTreeMap<String, u128>>,
+        contract_state: SpecState,
+        user_rewards: BTreeMap<String, SpecUser>,
+        #[serde(rename = "mbt::actionTaken")]
+        action_taken: String,
+        #[serde(rename = "mbt::nondetPicks")]
+        nondet_picks: NondetPicks,
+    }
+
+    /// `Transaction` of `brisingamenTwinTest`
+    #[derive(Debug, Deserialize)]
+    struct SpecTransaction {
+        kind: String,
+    }
+
+    /// The variables of `brisingamenTwinTest`
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct TwinVars {
+        #[serde(with = "As::<BTreeMap<Same, Integer>>")]
+        contract_balances: BTreeMap<String, u128>,
+        #[serde(with = "As::<BTreeMap<Same, BTreeMap<Same, Integer>>>")]
+        contract_balances_rewards: BTreeMap<String, BTreeMap<String, u128>>,
+        contract_contract_state: SpecState,
+        contract_user_rewards: BTreeMap<String, SpecUser>,
+        #[serde(with = "As::<BTreeMap<Same, Integer>>")]
+        twin_balances: BTreeMap<String, u128>,
+        #[serde(with = "As::<BTreeMap<Same, BTreeMap<Same, Integer>>>")]
+        twin_balances_rewards: BTreeMap<String, BTreeMap<String, u128>>,
+        twin_contract_state: SpecState,
+        twin_user_rewards: BTreeMap<String, SpecUser>,
+        last_tx: SpecTransaction,
+        #[serde(rename = "mbt::actionTaken")]
+        action_taken: String,
+        #[serde(rename = "mbt::nondetPicks")]
+        nondet_picks: NondetPicks,
+    }
+
+    impl TwinVars {
+        /// The variables of the original spec and of its twin
+        fn split(self) -> (Vars, Vars) {
+            let original = Vars {
+                balances: self.contract_balances,
+                balances_rewards: self.contract_balances_rewards,
+                contract_state: self.contract_contract_state,
+                user_rewards: self.contract_user_rewards,
+                action_taken: self.action_taken.clone(),
+                nondet_picks: self.nondet_picks.clone(),
+            };
+            let twin = Vars {
+                balances: self.twin_balances,
+                balances_rewards: self.twin_balances_rewards,
+                contract_state: self.twin_contract_state,
+                user_rewards: self.twin_user_rewards,
+                action_taken: self.action_taken,
+                nondet_picks: self.nondet_picks,
+            };
+            (original, twin)
+        }
+    }
+
+    struct Replay {
+        app: App,
+        contract_addr: Addr,
+    }
+
+    impl Replay {
+        /// Instantiates `contract` and mints the balances of the initial state
+        fn init(contract: Box<dyn Contract<Empty>>, vars: &Vars) -> Self {
+            let mut app = App::default();
+            let code_id = app.store_code(contract);
+            let contract_addr = app
+                .instantiate_contract(
+                    code_id,
+                    Addr::unchecked(vars.contract_state.owner.to_lowercase()),
+                    &InstantiateMsg {
+                        reward_denoms: SPEC_REWARD_DENOMS.map(String::from).to_vec(),
+                        ..instantiate_msg()
+                    },
+                    &[],
+                    "test",
+                    None,
+                )
+                .unwrap();
+
+            let mut replay = Replay { app, contract_addr };
+            for (addr, amount) in &vars.balances {
+                if *amount > 0 {
+                    let recipient = replay.addr(addr).to_string();
+                    replay.app = mint_tokens(replay.app, recipient, Uint128::new(*amount));
+                }
+            }
+            for (addr, amounts) in &vars.balances_rewards {
+                let amount: Vec<Coin> = amounts
+                    .iter()
+                    .filter(|(_, amount)| **amount > 0)
+                    .map(|(denom, amount)| coin(*amount, denom))
+                    .collect();
+                if !amount.is_empty() {
+                    let to_address = replay.addr(addr).to_string();
+                    replay
+                        .app
+                        .sudo(SudoMsg::Bank(BankSudo::Mint { to_address, amount }))
+                        .unwrap();
+                }
+            }
+            replay
+        }
+
+        /// Address of a spec address in the app, which only takes lowercase ones
+        fn addr(&self, addr: &str) -> Addr {
+            if addr == SPEC_CONTRACT {
+                self.contract_addr.clone()
+            } else {
+                Addr::unchecked(addr.to_lowercase())
+            }
+        }
+
+        /// Executes the step leading to `vars`
+        fn step(&mut self, vars: &Vars, step: &str) {
+            let picks = &vars.nondet_picks;
+            let sender = || picks.sender.clone().expect("missing nondet pick sender");
+            let denom = || picks.denom.clone().expect("missing nondet pick denom");
+            let amount = || {
+                let amount = picks.amount.as_ref().expect("missing nondet pick amount");
+                u128::try_from(amount).expect("amount out of the Uint128 range")
+            };
+
+            let (sender, msg, funds) = match vars.action_taken.as_str() {
+                "Deposit" => (
+                    sender(),
+                    ExecuteMsg::Deposit {},
+                    vec![coin(amount(), DENOM)],
+                ),
+                "Withdraw" => (
+                    sender(),
+                    ExecuteMsg::Withdraw {
+                        amount: Uint128::new(amount()),
+                    },
+                    vec![],
+                ),
+                // the spec calls it with the owner, which is not picked
+                "IncreaseReward" => (
+                    vars.contract_state.owner.clone(),
+                    ExecuteMsg::IncreaseReward {},
+                    vec![coin(amount(), denom())],
+                ),
+                "ClaimRewards" => (sender(), ExecuteMsg::ClaimRewards {}, vec![]),
+                action => panic!("unexpected action {action}"),
+            };
+
+            // the spec only takes steps which succeed
+            self.app
+                .execute_contract(self.addr(&sender), self.contract_addr.clone(), &msg, &funds)
+                .unwrap_or_else(|err| panic!("{step} by {sender} failed: {err}"));
+        }
+
+        /// Compares `STATE`, `USERS` and the bank balances to `vars` after `step`
+        fn check(&self, vars: &Vars, step: &str) {
+            // read the contract storage directly, to see every user
+            let mut storage = MockStorage::new();
+            for (key, value) in self.app.dump_wasm_raw(&self.contract_addr) {
+                storage.set(&key, &value);
+            }
+
+            let spec = &vars.contract_state;
+            // the spec has no reward streams, nor block time
+            let now = self.app.block_info().time;
+            let rewards = spec
+                .global_index
+                .iter()
+                .map(|(denom, global_index)| {
+                    let reward = Reward {
+                        global_index: Decimal::raw(*global_index),
+                        unallocated: Uint128::new(spec.unallocated[denom]),
+                        rate: Uint128::zero(),
+                        start: now,
+                        end: now,
+                    };
+                    (denom.clone(), reward)
+                })
+                .collect();
+            assert_eq!(
+                STATE.load(&storage).unwrap(),
+                State {
+                    owner: Some(self.addr(&spec.owner)),
+                    pending_owner: None,
+                    denom: DENOM.to_string(),
+                    compounding: false,
+                    unbonding_period: 0,
+                    rewards,
+                    last_update: now,
+                    total_staked: Uint128::new(spec.total_staked),
+                },
+                "STATE after {step}"
+            );
+
+            let users: BTreeMap<Addr, UserRewardInfo> = USERS
+                .range(&storage, None, None, Order::Ascending)
+                .collect::<Result<_, _>>()
+                .unwrap();
+            let expected: BTreeMap<Addr, UserRewardInfo> = vars
+                .user_rewards
+                .iter()
+                .map(|(addr, user)| {
+                    let rewards = user
+                        .rewards
+                        .iter()
+                        .map(|(denom, reward)| {
+                            let reward = UserReward {
+                                user_index: Decimal::raw(reward.user_index),
+                                pending_rewards: Uint128::new(reward.pending_rewards),
+                            };
+                            (denom.clone(), reward)
+                        })
+                        .collect();
+                    let user = UserRewardInfo {
+                        staked_amount: Uint128::new(user.staked_amount),
+                        rewards,
+                    };
+                    (self.addr(addr), user)
+                })
+                .collect();
+            assert_eq!(users, expected, "USERS after {step}");
+
+            let balances = vars
+                .balances
+                .iter()
+                .map(|(addr, amount)| (addr, DENOM, amount));
+            let balances_rewards = vars.balances_rewards.iter().flat_map(|(addr, amounts)| {
+                amounts
+                    .iter()
+                    .map(move |(denom, amount)| (addr, denom.as_str(), amount))
+            });
+            for (addr, denom, amount) in balances.chain(balances_rewards) {
+                let balance: Coin = self
+                    .app
+                    .wrap()
+                    .query_balance(self.addr(addr), denom)
+                    .unwrap();
+                assert_eq!(
+                    balance.amount,
+                    Uint128::new(*amount),
+                    "{denom} balance of {addr} after {step}"
+                );
+            }
+        }
+    }
+
+    /// Replays the trace in `path`, checking every state
+    fn replay(path: &Path) {
+        let trace = itf::trace_from_str::<Vars>(&fs::read_to_string(path).unwrap())
+            .unwrap_or_else(|err| panic!("can't read the trace {}: {err}", path.display()));
+        let mut states = trace.states.into_iter().map(|state| state.value);
+
+        let init = states.next().expect("empty trace");
+        assert_eq!(init.action_taken, "init");
+
+        let mut replay = Replay::init(challenge_contract(), &init);
+        replay.check(&init, &format!("init of {}", path.display()));
+
+        for (i, vars) in states.enumerate() {
+            let step = format!(
+                "step {} ({}) of {}",
+                i + 1,
+                vars.action_taken,
+                path.display()
+            );
+            replay.step(&vars, &step);
+            replay.check(&vars, &step);
+        }
+    }
+
+    /// Replays the twin trace in `path`, the original spec on `contract` and
+    /// its twin on `fixed`
+    fn replay_twin(path: &Path) {
+        let trace = itf::trace_from_str::<TwinVars>(&fs::read_to_string(path).unwrap())
+            .unwrap_or_else(|err| panic!("can't read the trace {}: {err}", path.display()));
+        let mut states = trace.states.into_iter();
+
+        // every init action of the spec records an "init" transaction, whatever
+        // `--init` names it, but only `initGenesis` starts from empty contracts
+        let init = states.next().expect("empty trace").value;
+        assert_eq!(init.last_tx.kind, "init");
+        assert!(
+            init.contract_user_rewards.is_empty() && init.twin_user_rewards.is_empty(),
+            "twin traces must start at initGenesis, unlike {}",
+            path.display()
+        );
+
+        let (init, twin_init) = init.split();
+        let states = states.map(|state| state.value.split());
+
+        let mut replay = Replay::init(challenge_contract(), &init);
+        let mut twin = Replay::init(fixed_contract(), &twin_init);
+        replay.check(&init, &format!("init of {}", path.display()));
+        twin.check(
+            &twin_init,
+            &format!("init of the twin of {}", path.display()),
+        );
+
+        for (i, (vars, twin_vars)) in states.enumerate() {
+            let step = format!(
+                "step {} ({}) of {}",
+                i + 1,
+                vars.action_taken,
+                path.display()
+            );
+            replay.step(&vars, &step);
+            replay.check(&vars, &step);
+            let step = format!("{step}, in the twin");
+            twin.step(&twin_vars, &step);
+            twin.check(&twin_vars, &step);
+        }
+    }
+
+    /// Runs `replay` on every trace in `dir`, which must hold at least one
+    fn replay_dir(dir: &str, replay: fn(&Path)) {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+        let entries = fs::read_dir(&dir)
+            .unwrap_or_else(|err| panic!("can't read the traces in {}: {err}", dir.display()));
+
+        let mut replayed = 0;
+        for entry in entries {
+            let path = entry.unwrap().path();
+            if path.to_string_lossy().ends_with(".itf.json") {
+                replay(&path);
+                replayed += 1;
+            }
+        }
+        assert!(replayed > 0, "no traces in {}", dir.display());
+    }
+
+    #[test]
+    fn replay_traces() {
+        replay_dir("traces", replay);
+    }
+
+    #[test]
+    fn replay_twin_traces() {
+        replay_dir("traces/twin", replay_twin);
+    }
+}
@@ -0,0 +1,103 @@
+//! Layouts stored by earlier versions and their conversion to `state`.
+//!
+//! Contracts instantiated before versions were recorded with `cw2` are
+//! taken to be 0.1.0, the only release without them.
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Addr, Order, StdResult, Storage, Timestamp, Uint128};
+use semver::Version;
+
+use crate::error::ContractError;
+use crate::state::{Reward, State, UserReward, UserRewardInfo, STATE, USERS};
+
+/// The layouts of 0.1, with the denoms hard-coded
+pub mod v0_1 {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{Addr, Decimal, Uint128};
+    use cw_storage_plus::{Item, Map};
+
+    pub const DENOM: &str = "uawesome";
+    pub const REWARD_DENOM: &str = "uoak";
+
+    #[cw_serde]
+    pub struct State {
+        pub owner: Addr,
+        pub total_staked: Uint128,
+        pub global_index: Decimal,
+    }
+
+    #[cw_serde]
+    pub struct UserRewardInfo {
+        pub staked_amount: Uint128,
+        pub user_index: Decimal,
+        pub pending_rewards: Uint128,
+    }
+
+    pub const STATE: Item<State> = Item::new("state");
+
+    pub const USERS: Map<&Addr, UserRewardInfo> = Map::new("users");
+}
+
+impl From<v0_1::State> for State {
+    fn from(state: v0_1::State) -> Self {
+        // rewards were distributed right away, never streamed
+        let reward = Reward {
+            global_index: state.global_index,
+            unallocated: Uint128::zero(),
+            rate: Uint128::zero(),
+            start: Timestamp::from_seconds(0),
+            end: Timestamp::from_seconds(0),
+        };
+        State {
+            owner: Some(state.owner),
+            pending_owner: None,
+            denom: v0_1::DENOM.to_string(),
+            compounding: false,
+            // withdrawals were paid out right away
+            unbonding_period: 0,
+            rewards: BTreeMap::from([(v0_1::REWARD_DENOM.to_string(), reward)]),
+            last_update: Timestamp::from_seconds(0),
+            total_staked: state.total_staked,
+        }
+    }
+}
+
+impl From<v0_1::UserRewardInfo> for UserRewardInfo {
+    fn from(user: v0_1::UserRewardInfo) -> Self {
+        let reward = UserReward {
+            user_index: user.user_index,
+            pending_rewards: user.pending_rewards,
+        };
+        UserRewardInfo {
+            staked_amount: user.staked_amount,
+            rewards: BTreeMap::from([(v0_1::REWARD_DENOM.to_string(), reward)]),
+        }
+    }
+}
+
+/// Version of the stored contract, which should be `name`
+pub fn stored_version(storage: &dyn Storage, name: &str) -> Result<Version, ContractError> {
+    match cw2::CONTRACT.may_load(storage)? {
+        Some(stored) if stored.contract != name => Err(ContractError::WrongContract {
+            contract: stored.contract,
+        }),
+        Some(stored) => Ok(stored.version.parse()?),
+        None => Ok(Version::new(0, 1, 0)),
+    }
+}
+
+/// Converts the layouts stored by version `from` to the current ones
+pub fn convert(storage: &mut dyn Storage, from: &Version) -> StdResult<()> {
+    if *from < Version::new(0, 2, 0) {
+        let state: State = v0_1::STATE.load(storage)?.into();
+        let users: Vec<(Addr, v0_1::UserRewardInfo)> = v0_1::USERS
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<_>>()?;
+
+        STATE.save(storage, &state)?;
+        for (addr, user) in users {
+            USERS.save(storage, &addr, &user.into())?;
+        }
+    }
+    Ok(())
+}
@@ -1,21 +1,51 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Addr, Coin, Timestamp, Uint128};
 
-use crate::state::{State, UserRewardInfo};
+use crate::state::{State, Unbonding, UserRewardInfo};
 
 #[cw_serde]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    /// The staked denom
+    pub denom: String,
+    /// The denoms rewards are paid in, which should not include `denom`
+    /// unless `compounding`
+    pub reward_denoms: Vec<String>,
+    /// Stake claimed rewards in `denom` rather than paying them out, with
+    /// `denom` among the reward denoms
+    pub compounding: bool,
+    /// Seconds withdrawn funds wait before `ClaimUnbonded`, zero to pay them
+    /// out right away
+    pub unbonding_period: u64,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    /// Owner increase global index reward
+    /// Owner increase global index reward of the reward denom sent along
     IncreaseReward {},
+    /// Owner streams rewards at `rate` per second from `start` to `end`,
+    /// sending all of them along in one of the reward denoms
+    StreamReward {
+        rate: Uint128,
+        start: Timestamp,
+        end: Timestamp,
+    },
     /// User deposits
     Deposit {},
-    /// User withdraws
+    /// User withdraws, unbonding the amount for the unbonding period
     Withdraw { amount: Uint128 },
-    /// User claim rewards
+    /// User claims the withdrawn funds done unbonding
+    ClaimUnbonded {},
+    /// User claim rewards of every reward denom
     ClaimRewards {},
+    /// Owner proposes a new owner, replacing any earlier proposal
+    ProposeOwner { owner: String },
+    /// Pending owner accepts the ownership
+    AcceptOwnership {},
+    /// Owner gives up the ownership, no one can increase rewards afterwards
+    RenounceOwnership {},
 }
 
 #[cw_serde]
@@ -25,7 +55,75 @@ pub enum QueryMsg {
     #[returns(State)]
     State {},
 
-    /// Query user reward information
+    /// Query user reward information, zeroed for unknown users
     #[returns(UserRewardInfo)]
     User { user: String },
+
+    /// Query the reward information of users, ordered by address
+    #[returns(UsersResponse)]
+    Users {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Query the rewards a user would claim now, by reward denom
+    #[returns(SimulateClaimResponse)]
+    SimulateClaim { user: String },
+
+    /// Query the rewards owed to all users by reward denom, going through
+    /// every one of them
+    #[returns(TotalPendingRewardsResponse)]
+    TotalPendingRewards {},
+
+    /// Query the funds a user is unbonding
+    #[returns(UnbondingsResponse)]
+    Unbondings { user: String },
+
+    /// Query the denoms and unbonding period of the pool
+    #[returns(ConfigResponse)]
+    Config {},
+
+    /// Query the current and pending owner
+    #[returns(OwnershipResponse)]
+    Ownership {},
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub denom: String,
+    pub reward_denoms: Vec<String>,
+    pub compounding: bool,
+    pub unbonding_period: u64,
+}
+
+#[cw_serde]
+pub struct UserEntry {
+    pub address: Addr,
+    pub info: UserRewardInfo,
+}
+
+#[cw_serde]
+pub struct UsersResponse {
+    pub users: Vec<UserEntry>,
+}
+
+#[cw_serde]
+pub struct SimulateClaimResponse {
+    pub rewards: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct TotalPendingRewardsResponse {
+    pub rewards: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct UnbondingsResponse {
+    pub unbondings: Vec<Unbonding>,
+}
+
+#[cw_serde]
+pub struct OwnershipResponse {
+    pub owner: Option<Addr>,
+    pub pending_owner: Option<Addr>,
 }
@@ -0,0 +1,411 @@
+/// Property-based tests: random sequences of `ExecuteMsg`, sent by several
+/// users with random funds, checking the invariants of the contract after
+/// every step. Failing sequences are shrunk by `proptest`.
+///
+/// Both variants break some invariants on purpose, see `Known`: the original
+/// and `fixed` alike keep funds sent along with messages which don't take
+/// any, and only the original owes re-entering users more than was deposited.
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        integration_tests::tests::{
+            challenge_contract, fixed_contract, instantiate_msg, BONUS_DENOM, DENOM, OWNER,
+            REWARD_DENOM, USER, USER2,
+        },
+        msg::{
+            ExecuteMsg, InstantiateMsg, QueryMsg, TotalPendingRewardsResponse, UnbondingsResponse,
+            UsersResponse,
+        },
+        state::State,
+    };
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use cosmwasm_std::{coin, Addr, Coin, Empty, Timestamp, Uint128};
+    use cw_multi_test::{App, BankSudo, Contract, Executor, SudoMsg};
+    use proptest::{collection::vec, prelude::*};
+
+    /// A denom the contract knows nothing about
+    const OTHER_DENOM: &str = "uother";
+
+    const REWARD_DENOMS: [&str; 2] = [REWARD_DENOM, BONUS_DENOM];
+
+    const SENDERS: [&str; 4] = [OWNER, USER, USER2, "user3"];
+
+    /// What every sender starts with, of every denom
+    const INITIAL_FUNDS: u128 = 1_000_000;
+
+    /// Seconds withdrawn funds unbond for
+    const UNBONDING_PERIOD: u64 = 30;
+
+    /// Invariants a variant is known to break, which `run` then tolerates
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    enum Known {
+        /// messages which don't take funds keep them, so the contract holds
+        /// more than `total_staked`, checked as holding exactly that much more
+        StrayFunds,
+        /// a user who withdraws everything and deposits again is owed the
+        /// rewards distributed in between, so more is owed than was deposited
+        ReentryRewards,
+    }
+
+    #[derive(Clone, Debug)]
+    struct Step {
+        /// seconds the block time moves on before the step
+        wait: u64,
+        sender: &'static str,
+        /// `start` and `end` of a `StreamReward` are seconds from the block time
+        msg: ExecuteMsg,
+        funds: Vec<Coin>,
+    }
+
+    fn any_coin() -> impl Strategy<Value = Coin> {
+        let denom = prop_oneof![
+            Just(DENOM),
+            Just(REWARD_DENOM),
+            Just(BONUS_DENOM),
+            Just(OTHER_DENOM)
+        ];
+        (1..=1_000u128, denom).prop_map(|(amount, denom)| coin(amount, denom))
+    }
+
+    fn step() -> impl Strategy<Value = Step> {
+        let sender = || proptest::sample::select(&SENDERS[..]);
+        let msg = prop_oneof![
+            3 => Just(ExecuteMsg::IncreaseReward {}),
+            3 => (1..=10u128, 0..=20u64, 1..=50u64).prop_map(|(rate, start, duration)| {
+                ExecuteMsg::StreamReward {
+                    rate: Uint128::new(rate),
+                    start: Timestamp::from_seconds(start),
+                    end: Timestamp::from_seconds(start + duration),
+                }
+            }),
+            3 => Just(ExecuteMsg::Deposit {}),
+            3 => (0..=1_000u128).prop_map(|amount| ExecuteMsg::Withdraw {
+                amount: Uint128::new(amount)
+            }),
+            3 => Just(ExecuteMsg::ClaimUnbonded {}),
+            3 => Just(ExecuteMsg::ClaimRewards {}),
+            2 => sender().prop_map(|owner| ExecuteMsg::ProposeOwner {
+                owner: owner.to_string()
+            }),
+            2 => Just(ExecuteMsg::AcceptOwnership {}),
+            // rarely, as no rewards come in afterwards
+            1 => Just(ExecuteMsg::RenounceOwnership {}),
+        ];
+        let reward_denom = proptest::sample::select(&REWARD_DENOMS[..]);
+        (0..=20u64, sender(), reward_denom, msg).prop_flat_map(
+            |(wait, sender, reward_denom, msg)| {
+                let asked = match &msg {
+                    ExecuteMsg::Deposit {} => (1..=1_000u128)
+                        .prop_map(|amount| vec![coin(amount, DENOM)])
+                        .boxed(),
+                    ExecuteMsg::IncreaseReward {} => (1..=1_000u128)
+                        .prop_map(move |amount| vec![coin(amount, reward_denom)])
+                        .boxed(),
+                    ExecuteMsg::StreamReward { rate, start, end } => {
+                        let seconds = end.seconds() - start.seconds();
+                        let amount = rate.u128() * u128::from(seconds);
+                        Just(vec![coin(amount, reward_denom)]).boxed()
+                    }
+                    _ => Just(vec![]).boxed(),
+                };
+                // mostly the funds the message asks for, sometimes anything
+                let funds = prop_oneof![3 => asked, 1 => vec(any_coin(), 0..=2)];
+                (Just(wait), Just(sender), Just(msg), funds).prop_map(
+                    |(wait, sender, msg, funds)| Step {
+                        wait,
+                        sender,
+                        msg,
+                        funds,
+                    },
+                )
+            },
+        )
+    }
+
+    fn steps() -> impl Strategy<Value = Vec<Step>> {
+        vec(step(), 1..40)
+    }
+
+    /// Instantiates `contract` and funds every sender
+    fn setup(contract: Box<dyn Contract<Empty>>) -> (App, Addr) {
+        let mut app = App::default();
+        let code_id = app.store_code(contract);
+        let contract_addr = app
+            .instantiate_contract(
+                code_id,
+                Addr::unchecked(OWNER),
+                &InstantiateMsg {
+                    reward_denoms: REWARD_DENOMS.map(String::from).to_vec(),
+                    unbonding_period: UNBONDING_PERIOD,
+                    ..instantiate_msg()
+                },
+                &[],
+                "test",
+                None,
+            )
+            .unwrap();
+
+        for sender in SENDERS {
+            app.sudo(SudoMsg::Bank(BankSudo::Mint {
+                to_address: sender.to_owned(),
+                amount: [DENOM, REWARD_DENOM, BONUS_DENOM, OTHER_DENOM]
+                    .map(|denom| coin(INITIAL_FUNDS, denom))
+                    .to_vec(),
+            }))
+            .unwrap();
+        }
+        (app, contract_addr)
+    }
+
+    fn balance(app: &App, addr: &str, denom: &str) -> Uint128 {
+        app.wrap().query_balance(addr, denom).unwrap().amount
+    }
+
+    /// Stake of all the users, paging through them a few at a time
+    fn total_staked_by_users(app: &App, contract_addr: &Addr) -> Uint128 {
+        let mut staked = Uint128::zero();
+        let mut start_after = None;
+        loop {
+            let page: UsersResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr,
+                    &QueryMsg::Users {
+                        start_after: start_after.take(),
+                        limit: Some(2),
+                    },
+                )
+                .unwrap();
+            let Some(last) = page.users.last() else {
+                return staked;
+            };
+            start_after = Some(last.address.to_string());
+            staked += page
+                .users
+                .iter()
+                .map(|user| user.info.staked_amount)
+                .sum::<Uint128>();
+        }
+    }
+
+    /// Runs `steps` on `contract`, failing on the first broken invariant
+    /// which isn't `known`, and returns the known ones which were broken
+    fn run(
+        contract: Box<dyn Contract<Empty>>,
+        steps: Vec<Step>,
+        known: &[Known],
+    ) -> Result<BTreeSet<Known>, TestCaseError> {
+        let (mut app, contract_addr) = setup(contract);
+        // rewards of each denom paid in through `IncreaseReward` and out through
+        // `ClaimRewards`, with what is still owed never more than the difference
+        let mut deposited: BTreeMap<&str, Uint128> = BTreeMap::new();
+        let mut claimed: BTreeMap<&str, Uint128> = BTreeMap::new();
+        // funds kept from messages which don't take any, by denom
+        let mut kept: BTreeMap<String, Uint128> = BTreeMap::new();
+        let mut broken = BTreeSet::new();
+
+        for (i, step) in steps.into_iter().enumerate() {
+            app.update_block(|block| block.time = block.time.plus_seconds(step.wait));
+            let msg = match step.msg {
+                ExecuteMsg::StreamReward { rate, start, end } => {
+                    let now = app.block_info().time;
+                    ExecuteMsg::StreamReward {
+                        rate,
+                        start: now.plus_seconds(start.seconds()),
+                        end: now.plus_seconds(end.seconds()),
+                    }
+                }
+                msg => msg,
+            };
+
+            let before = REWARD_DENOMS.map(|denom| balance(&app, step.sender, denom));
+            let executed = app.execute_contract(
+                Addr::unchecked(step.sender),
+                contract_addr.clone(),
+                &msg,
+                &step.funds,
+            );
+            // failing messages are reverted, so only successful ones count
+            if executed.is_ok() {
+                // the contract checks what these are paid
+                if !matches!(
+                    msg,
+                    ExecuteMsg::Deposit {}
+                        | ExecuteMsg::IncreaseReward {}
+                        | ExecuteMsg::StreamReward { .. }
+                ) {
+                    for funds in &step.funds {
+                        *kept.entry(funds.denom.clone()).or_default() += funds.amount;
+                    }
+                }
+                for (denom, before) in REWARD_DENOMS.into_iter().zip(before) {
+                    let after = balance(&app, step.sender, denom);
+                    // funds sent along are not part of what was claimed
+                    let sent: Uint128 = step
+                        .funds
+                        .iter()
+                        .filter(|funds| funds.denom == denom)
+                        .map(|funds| funds.amount)
+                        .sum();
+                    match msg {
+                        ExecuteMsg::IncreaseReward {} | ExecuteMsg::StreamReward { .. } => {
+                            *deposited.entry(denom).or_default() += before - after
+                        }
+                        ExecuteMsg::ClaimRewards {} => {
+                            *claimed.entry(denom).or_default() += after + sent - before
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            let state: State = app
+                .wrap()
+                .query_wasm_smart(&contract_addr, &QueryMsg::State {})
+                .unwrap();
+            let staked = total_staked_by_users(&app, &contract_addr);
+            let pending: TotalPendingRewardsResponse = app
+                .wrap()
+                .query_wasm_smart(&contract_addr, &QueryMsg::TotalPendingRewards {})
+                .unwrap();
+
+            prop_assert_eq!(state.total_staked, staked, "total_staked after step {}", i);
+            for pending in pending.rewards {
+                let denom = pending.denom.as_str();
+                let claimed = claimed.get(denom).copied().unwrap_or_default();
+                let deposited = deposited.get(denom).copied().unwrap_or_default();
+                if claimed + pending.amount > deposited {
+                    prop_assert!(
+                        known.contains(&Known::ReentryRewards),
+                        "claimed {} and owed {} out of {} {} after step {}",
+                        claimed,
+                        pending.amount,
+                        deposited,
+                        denom,
+                        i
+                    );
+                    broken.insert(Known::ReentryRewards);
+                }
+            }
+
+            if !kept.is_empty() {
+                prop_assert!(
+                    known.contains(&Known::StrayFunds),
+                    "kept {:?} after step {}",
+                    kept,
+                    i
+                );
+                broken.insert(Known::StrayFunds);
+            }
+
+            // withdrawn funds are held until claimed
+            let unbonding: Uint128 = SENDERS
+                .iter()
+                .flat_map(|sender| {
+                    let unbondings: UnbondingsResponse = app
+                        .wrap()
+                        .query_wasm_smart(
+                            &contract_addr,
+                            &QueryMsg::Unbondings {
+                                user: sender.to_string(),
+                            },
+                        )
+                        .unwrap();
+                    unbondings.unbondings
+                })
+                .map(|unbonding| unbonding.amount)
+                .sum();
+            prop_assert_eq!(
+                balance(&app, contract_addr.as_str(), DENOM),
+                state.total_staked + unbonding + kept.get(DENOM).copied().unwrap_or_default(),
+                "{} balance after step {}",
+                DENOM,
+                i
+            );
+        }
+        Ok(broken)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn fixed_keeps_invariants(steps in steps()) {
+            run(fixed_contract(), steps, &[Known::StrayFunds])?;
+        }
+
+        #[test]
+        fn original_keeps_invariants(steps in steps()) {
+            run(challenge_contract(), steps, &[Known::StrayFunds, Known::ReentryRewards])?;
+        }
+    }
+
+    fn send(sender: &'static str, msg: ExecuteMsg, funds: &[Coin]) -> Step {
+        Step {
+            wait: 0,
+            sender,
+            msg,
+            funds: funds.to_vec(),
+        }
+    }
+
+    /// `USER` staking, withdrawing everything while rewards are increased, and
+    /// staking again next to `USER2`
+    fn reentry() -> Vec<Step> {
+        vec![
+            send(USER, ExecuteMsg::Deposit {}, &[coin(100, DENOM)]),
+            send(USER2, ExecuteMsg::Deposit {}, &[coin(100, DENOM)]),
+            send(
+                OWNER,
+                ExecuteMsg::IncreaseReward {},
+                &[coin(100, REWARD_DENOM)],
+            ),
+            send(
+                USER,
+                ExecuteMsg::Withdraw {
+                    amount: Uint128::new(100),
+                },
+                &[],
+            ),
+            send(
+                OWNER,
+                ExecuteMsg::IncreaseReward {},
+                &[coin(100, REWARD_DENOM)],
+            ),
+            send(USER, ExecuteMsg::Deposit {}, &[coin(100, DENOM)]),
+        ]
+    }
+
+    #[test]
+    fn original_owes_reentry_rewards() {
+        let broken = run(challenge_contract(), reentry(), &[Known::ReentryRewards]).unwrap();
+        assert_eq!(broken, BTreeSet::from([Known::ReentryRewards]));
+    }
+
+    #[test]
+    fn fixed_owes_no_reentry_rewards() {
+        let broken = run(fixed_contract(), reentry(), &[]).unwrap();
+        assert_eq!(broken, BTreeSet::new());
+    }
+
+    /// Funds sent along with `Withdraw` and `ClaimRewards` are kept by both
+    #[test]
+    fn variants_keep_stray_funds() {
+        for contract in [challenge_contract, fixed_contract] {
+            let steps = vec![
+                send(USER, ExecuteMsg::Deposit {}, &[coin(100, DENOM)]),
+                send(
+                    USER,
+                    ExecuteMsg::Withdraw {
+                        amount: Uint128::new(100),
+                    },
+                    &[coin(10, DENOM)],
+                ),
+                send(OWNER, ExecuteMsg::ClaimRewards {}, &[coin(10, OTHER_DENOM)]),
+            ];
+            let broken = run(contract(), steps, &[Known::StrayFunds]).unwrap();
+            assert_eq!(broken, BTreeSet::from([Known::StrayFunds]));
+        }
+    }
+}
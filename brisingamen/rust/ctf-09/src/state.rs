@@ -1,24 +1,80 @@
+use std::collections::BTreeMap;
+
 use cosmwasm_schema::cw_serde;
 
-use cosmwasm_std::{Addr, Decimal, Uint128};
+use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map};
 
 // ANCHOR[id=State]
 #[cw_serde]
 pub struct State {
-    pub owner: Addr,
+    /// `None` once ownership is renounced
+    pub owner: Option<Addr>,
+    /// proposed by the owner, who becomes owner by accepting
+    pub pending_owner: Option<Addr>,
+    /// the staked denom
+    pub denom: String,
+    /// whether claimed rewards in `denom` are staked rather than paid out
+    pub compounding: bool,
+    /// seconds withdrawn funds wait before they can be claimed, zero to pay
+    /// them out right away
+    pub unbonding_period: u64,
+    /// the whitelisted reward denoms, each with its own index
+    pub rewards: BTreeMap<String, Reward>,
+    /// when rewards were last accrued
+    pub last_update: Timestamp,
     pub total_staked: Uint128,
+}
+
+// ANCHOR[id=Reward]
+#[cw_serde]
+pub struct Reward {
     pub global_index: Decimal,
+    /// received but not in `global_index` yet, while nobody is staked
+    pub unallocated: Uint128,
+    /// streamed per second from `start` to `end`
+    pub rate: Uint128,
+    pub start: Timestamp,
+    pub end: Timestamp,
 }
 
 // ANCHOR[id=UserRewardInfo]
 #[cw_serde]
+#[derive(Default)]
 pub struct UserRewardInfo {
     pub staked_amount: Uint128,
+    /// by reward denom
+    pub rewards: BTreeMap<String, UserReward>,
+}
+
+// ANCHOR[id=UserReward]
+#[cw_serde]
+#[derive(Default)]
+pub struct UserReward {
     pub user_index: Decimal,
     pub pending_rewards: Uint128,
 }
 
+impl UserReward {
+    /// Nothing pending, only earning from the current index of `reward`
+    pub fn starting_at(reward: &Reward) -> Self {
+        UserReward {
+            user_index: reward.global_index,
+            pending_rewards: Uint128::zero(),
+        }
+    }
+}
+
 pub const STATE: Item<State> = Item::new("state");
 
 pub const USERS: Map<&Addr, UserRewardInfo> = Map::new("users");
+
+// ANCHOR[id=Unbonding]
+#[cw_serde]
+pub struct Unbonding {
+    pub amount: Uint128,
+    pub release_at: Timestamp,
+}
+
+/// withdrawn funds of every user, in the order they were withdrawn
+pub const UNBONDINGS: Map<&Addr, Vec<Unbonding>> = Map::new("unbondings");